@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use segment_colouring::linear_axis::{
+    clicqued::ClicquedLinearAxis,
+    game::{Game, GameAxis},
+    normalization::NormalizationCache,
+};
+
+/// `cargo run --release -- 5 3 7` from the README, and a couple of larger boards.
+const CASES: [(usize, usize, usize); 3] = [(5, 3, 7), (5, 3, 9), (5, 3, 11)];
+const RUNS: u32 = 20;
+
+fn main() {
+    for (colours, clicque, events) in CASES {
+        for cache in [None, Some(false), Some(true)] {
+            let mut elapsed = Duration::ZERO;
+            let mut states = 0;
+            let mut hits = 0;
+            for _ in 0..RUNS {
+                let mut game = Game::new(events, clicque, colours, None);
+                game.normalization_cache =
+                    cache.map(|checked| NormalizationCache::new(12, checked));
+                let start = Instant::now();
+                game.simulate(-1);
+                elapsed += start.elapsed();
+                states = game.number_of_states();
+                hits = game.normalization_cache.map_or(0, |c| c.hits);
+            }
+            println!(
+                "{colours} {clicque} {events} cache {cache:?}: {states} states in {:?} per run, {:.0} states/s, {hits} cache hits",
+                elapsed / RUNS,
+                (states as u32 * RUNS) as f64 / elapsed.as_secs_f64()
+            );
+        }
+        keys(colours, clicque, events);
+    }
+}
+
+/// Replays the moves of the search and looks every position up in a table, the mirror too on a miss,
+/// once keyed by a `NormalizedState` as before `StateKey`, and once by a `StateKey`.
+fn keys(colours: usize, clicque: usize, events: usize) {
+    let mut game = Game::new(events, clicque, colours, None);
+    game.trace = Some(vec![]);
+    game.simulate(-1);
+    let trace = game.trace.unwrap();
+    let mut vectors = Duration::ZERO;
+    let mut packed = Duration::ZERO;
+    for _ in 0..RUNS {
+        let mut axis = ClicquedLinearAxis::new(clicque);
+        let mut table = HashMap::new();
+        let start = Instant::now();
+        for &mv in &trace {
+            axis.apply_history(mv).unwrap();
+            let state = axis.strategy_normalize_without_symmetry();
+            if !table.contains_key(&state) && !table.contains_key(&axis.flipped(&state)) {
+                table.insert(state, ());
+            }
+        }
+        vectors += start.elapsed();
+
+        let mut axis = ClicquedLinearAxis::new(clicque);
+        let mut table = HashMap::new();
+        let start = Instant::now();
+        for &mv in &trace {
+            axis.apply_history(mv).unwrap();
+            let key = axis.normalize_key();
+            if !table.contains_key(&key) && !table.contains_key(&axis.flipped_key(&key)) {
+                table.insert(key, ());
+            }
+        }
+        packed += start.elapsed();
+    }
+    println!(
+        "{colours} {clicque} {events} keys: {} lookups in {:?} per run with NormalizedState, {:?} with StateKey",
+        trace.len(),
+        vectors / RUNS,
+        packed / RUNS
+    );
+}
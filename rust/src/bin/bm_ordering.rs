@@ -0,0 +1,29 @@
+use std::time::Instant;
+
+use clap::ValueEnum;
+use segment_colouring::linear_axis::{game::Game, ordering::MoveOrdering};
+
+/// How many positions each move ordering expands, against going from the left.
+const CASES: [(usize, usize, usize); 3] = [(5, 3, 7), (5, 3, 9), (5, 3, 11)];
+
+fn main() {
+    for (colours, clicque, events) in CASES {
+        let mut index_nodes = 0;
+        for &ordering in MoveOrdering::value_variants() {
+            let mut game = Game::new(events, clicque, colours, None);
+            game.move_ordering = ordering;
+            let start = Instant::now();
+            let result = game.simulate(-1);
+            let elapsed = start.elapsed();
+            if ordering == MoveOrdering::Index {
+                index_nodes = game.nodes();
+            }
+            println!(
+                "{colours} {clicque} {events} {ordering:?}: {result}, {} states, {} nodes ({:+.1}%) in {elapsed:?}",
+                game.number_of_states(),
+                game.nodes(),
+                (game.nodes() as f64 / index_nodes as f64 - 1.0) * 100.0
+            );
+        }
+    }
+}
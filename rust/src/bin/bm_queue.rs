@@ -0,0 +1,44 @@
+use std::time::{Duration, Instant};
+
+use segment_colouring::linear_axis::{clicqued::ClicquedLinearAxis, game::Game};
+
+/// Replays the moves of real searches on a fresh board, once with `--features linked-queue` and once without.
+const CASES: [(usize, usize, usize); 3] = [(5, 3, 7), (5, 3, 9), (5, 3, 11)];
+const RUNS: u32 = 20;
+
+fn main() {
+    let queue = if cfg!(feature = "linked-queue") {
+        "linked list"
+    } else {
+        "contiguous"
+    };
+    for (colours, clicque, events) in CASES {
+        let mut game = Game::new(events, clicque, colours, None);
+        game.trace = Some(vec![]);
+        game.simulate(-1);
+        let trace = game.trace.unwrap();
+        let mut moves_only = Duration::ZERO;
+        let mut with_keys = Duration::ZERO;
+        for _ in 0..RUNS {
+            let mut axis = ClicquedLinearAxis::new(clicque);
+            let start = Instant::now();
+            for &mv in &trace {
+                axis.apply_history(mv).unwrap();
+            }
+            moves_only += start.elapsed();
+            let mut axis = ClicquedLinearAxis::new(clicque);
+            let start = Instant::now();
+            for &mv in &trace {
+                axis.apply_history(mv).unwrap();
+                std::hint::black_box(axis.normalize_key());
+            }
+            with_keys += start.elapsed();
+        }
+        println!(
+            "{colours} {clicque} {events} {queue}: {} moves in {:?} per run, {:?} normalizing after each",
+            trace.len(),
+            moves_only / RUNS,
+            with_keys / RUNS
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::*;
+use segment_colouring::linear_axis::game::parse_max_events;
+use segment_colouring::linear_axis::jobs::{Job, JobResult, Split};
+use segment_colouring::linear_axis::strategy::{Strategy, StrategyConsumer};
+
+/// Solving a job recurses as deep as the full search, see the main binary.
+const STACK_SIZE: usize = 8 * 1024 * 1024 * 1024;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "Jobs",
+    about = "Splits the segment colouring game into jobs that separate processes can solve, and merges what they found."
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Expands the empty board a few moves and writes every position left to search as a job file.
+    Split {
+        desired_number_of_colours: usize,
+        max_clicque: usize,
+        #[arg(value_parser = parse_max_events)]
+        max_events: usize,
+        /// How many Spoiler moves to expand, more plies make more and smaller jobs.
+        #[arg(long, default_value_t = 2)]
+        plies: usize,
+        /// Where to write the jobs.
+        #[arg(long, default_value = "./jobs")]
+        dir: String,
+    },
+    /// Solves a job, writing its result and strategy next to it.
+    SolveJob {
+        job: String,
+        /// Give up after this many seconds.
+        #[arg(long)]
+        time_limit: Option<f64>,
+        /// Give up after expanding this many positions.
+        #[arg(long)]
+        node_limit: Option<usize>,
+    },
+    /// Combines the results and strategies of the jobs into those of the whole game.
+    Merge {
+        #[arg(long, default_value = "./jobs")]
+        dir: String,
+        /// Where to write the strategy of the whole game.
+        #[arg(long, default_value = "./rust.strategy")]
+        output: String,
+    },
+}
+
+fn split(split: Split, dir: &Path) {
+    fs::create_dir_all(dir).unwrap();
+    split
+        .write(&mut BufWriter::new(
+            File::create(dir.join("split")).unwrap(),
+        ))
+        .unwrap();
+    let start = Instant::now();
+    let jobs = split.jobs();
+    for (i, job) in jobs.iter().enumerate() {
+        let path = dir.join(format!("{i:05}.job"));
+        job.write(&mut BufWriter::new(File::create(path).unwrap()))
+            .unwrap();
+    }
+    println!(
+        "Wrote {} jobs to {} in {:?}.",
+        jobs.len(),
+        dir.display(),
+        start.elapsed()
+    );
+}
+
+fn solve_job(path: &Path, time_limit: Option<f64>, node_limit: Option<usize>) {
+    let job = Job::read(&mut BufReader::new(File::open(path).unwrap())).unwrap();
+    let strategy = StrategyConsumer::new(
+        job.force_num_colours * 2,
+        job.max_clicque,
+        job.force_num_colours,
+        Box::new(BufWriter::new(
+            File::create(path.with_extension("strategy")).unwrap(),
+        )),
+    );
+    let mut game = job.game(Some(strategy));
+    game.limits.time = time_limit.map(Duration::from_secs_f64);
+    game.limits.nodes = node_limit;
+    let start = Instant::now();
+    let result = JobResult::solve(&mut game);
+    result
+        .write(&mut File::create(path.with_extension("result")).unwrap())
+        .unwrap();
+    println!(
+        "{}: {:?} after expanding {} positions in {:?}.",
+        job.state,
+        result,
+        game.nodes(),
+        start.elapsed()
+    );
+}
+
+fn merge(dir: &Path, output: &str) {
+    let split = Split::read(&mut BufReader::new(File::open(dir.join("split")).unwrap())).unwrap();
+    let mut paths = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "job"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    let mut jobs = vec![];
+    let mut strategies = vec![];
+    for path in paths {
+        let job = Job::read(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+        let result = match File::open(path.with_extension("result")) {
+            Ok(file) => JobResult::read(&mut BufReader::new(file)).unwrap(),
+            Err(_) => JobResult::Unknown,
+        };
+        if result == JobResult::Win {
+            let file = File::open(path.with_extension("strategy")).unwrap();
+            strategies.push(Strategy::read(&mut BufReader::new(file)).unwrap());
+        }
+        jobs.push((job, result));
+    }
+    let unsolved = jobs
+        .iter()
+        .filter(|(_, result)| *result == JobResult::Unknown)
+        .count();
+    let strategy = StrategyConsumer::new(
+        split.force_num_colours * 2,
+        split.max_clicque,
+        split.force_num_colours,
+        Box::new(BufWriter::new(File::create(output).unwrap())),
+    );
+    match split.merge(&jobs, &strategies, Some(strategy)) {
+        JobResult::Win => println!(
+            "SUCCESS! {} colours can be forced with clicques of at most {}, the strategy is in {}.",
+            split.force_num_colours, split.max_clicque, output
+        ),
+        JobResult::Loss { colours } => println!(
+            "FAILURE! No way was found to force {} colours with clicques of at most {} and at most {} events, only {}. Searching with another move ordering may still find one.",
+            split.force_num_colours, split.max_clicque, split.max_events, colours
+        ),
+        JobResult::Unknown => println!(
+            "UNKNOWN! {} of the {} jobs are yet to be solved.",
+            unsolved,
+            jobs.len()
+        ),
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    let child = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || match args.command {
+            Command::Split {
+                desired_number_of_colours,
+                max_clicque,
+                max_events,
+                plies,
+                dir,
+            } => split(
+                Split {
+                    max_clicque,
+                    force_num_colours: desired_number_of_colours,
+                    max_events,
+                    plies,
+                },
+                Path::new(&dir),
+            ),
+            Command::SolveJob {
+                job,
+                time_limit,
+                node_limit,
+            } => solve_job(Path::new(&job), time_limit, node_limit),
+            Command::Merge { dir, output } => merge(Path::new(&dir), &output),
+        })
+        .unwrap();
+
+    child.join().unwrap();
+}
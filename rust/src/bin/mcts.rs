@@ -0,0 +1,58 @@
+use std::time::Instant;
+
+use clap::*;
+use segment_colouring::linear_axis::mcts::{Mcts, Rollout};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "MCTS",
+    about = "Looks for promising Spoiler lines with Monte Carlo tree search, for parameters too large to solve."
+)]
+struct Args {
+    desired_number_of_colours: usize,
+    max_clicque: usize,
+    max_events: usize,
+    #[arg(long, default_value_t = 100_000)]
+    iterations: usize,
+    /// The same seed gives the same search.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// How the colouring algorithm plays past the tree.
+    #[arg(long, value_enum, default_value_t = Rollout::Random)]
+    rollout: Rollout,
+    #[arg(long, default_value_t = std::f64::consts::SQRT_2)]
+    exploration: f64,
+    /// How many of Spoiler's first moves to show.
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut search = Mcts::new(
+        args.max_events,
+        args.max_clicque,
+        args.desired_number_of_colours,
+        args.seed,
+    );
+    search.rollout = args.rollout;
+    search.exploration = args.exploration;
+    let start = Instant::now();
+    search.run(args.iterations);
+    println!(
+        "{} playouts in {:?}, the most colours reached was {}.",
+        search.iterations(),
+        start.elapsed(),
+        search.most_colours
+    );
+    for stats in search.best_moves().into_iter().take(args.top) {
+        println!(
+            "{:?}: {} visits, {:.2} colours on average, at most {}",
+            stats.mov, stats.visits, stats.mean_colours, stats.most_colours
+        );
+    }
+    println!("Principal line:");
+    for mv in search.principal_line() {
+        println!("  {mv:?}");
+    }
+}
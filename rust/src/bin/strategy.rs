@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use clap::*;
+use segment_colouring::linear_axis::clicqued::ClicquedLinearAxis;
+use segment_colouring::linear_axis::strategy::{Strategy, StrategyState};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "Strategy",
+    about = "Queries, merges and compares the strategy files written by the simulation."
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Merges strategies of the same game. Where they play differently, keeps the first move that still wins.
+    /// Moves outside the plain game cannot be checked, conflicts with them are marked `?`.
+    Merge {
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Where to write the merged strategy.
+        #[arg(long, default_value = "./rust.strategy")]
+        output: String,
+    },
+    /// Lists the positions only one of the strategies plays in, the ones they play differently in,
+    /// and the first position from the empty board where they part.
+    Diff { left: String, right: String },
+    /// Prints Spoiler's move in a position, e.g. `A[BCabAcDaC]dc`, as a line of the strategy file.
+    /// The position may be written with its colours in any order, or in a mirror.
+    Lookup { file: String, state: String },
+}
+
+fn read(path: &str) -> Strategy {
+    Strategy::read(&mut BufReader::new(File::open(path).unwrap())).unwrap()
+}
+
+fn merge(files: &[String], output: &str) {
+    let strategies = files.iter().map(|path| read(path)).collect::<Vec<_>>();
+    let (merged, conflicts) = match Strategy::merge(&strategies) {
+        Ok(merged) => merged,
+        Err(error) => {
+            eprintln!("Cannot merge the strategies: {error}.");
+            std::process::exit(2);
+        }
+    };
+    for conflict in &conflicts {
+        println!("Conflicting moves in {}:", conflict.state.to_string());
+        for &mov in &conflict.moves {
+            let kept = match conflict.kept {
+                Some(kept) if kept == mov => "*",
+                None if !conflict.verified && mov == conflict.moves[0] => "?",
+                _ => " ",
+            };
+            println!("{} {}", kept, conflict.state.strategy_line(mov));
+        }
+    }
+    let unresolved = conflicts.iter().filter(|c| c.kept.is_none()).count();
+    let unverified = conflicts.iter().filter(|c| !c.verified).count();
+    println!(
+        "Merged {} positions, {} played differently, {} of them with no winning move, {} of those unverified.",
+        merged.moves.len(),
+        conflicts.len(),
+        unresolved,
+        unverified
+    );
+    if merged.wins_from(&mut ClicquedLinearAxis::new(merged.max_clicque)) {
+        println!("The merged strategy wins from the empty board.");
+    } else {
+        println!("The merged strategy does NOT win from the empty board.");
+    }
+    merged
+        .write(&mut BufWriter::new(File::create(output).unwrap()))
+        .unwrap();
+}
+
+fn diff(left_path: &str, right_path: &str) {
+    let (left, right) = (read(left_path), read(right_path));
+    let diff = left.diff(&right);
+    let only = |states: &[StrategyState], strategy: &Strategy, path: &str| {
+        println!("{} positions only in {}:", states.len(), path);
+        for state in states {
+            println!("  {}", state.strategy_line(strategy.moves[state]));
+        }
+    };
+    only(&diff.only_left, &left, left_path);
+    only(&diff.only_right, &right, right_path);
+    println!("{} positions played differently:", diff.different.len());
+    for (state, mine, theirs) in &diff.different {
+        println!("< {}", state.strategy_line(*mine));
+        println!("> {}", state.strategy_line(*theirs));
+    }
+    let Some(divergence) = diff.divergence else {
+        println!("The strategies play the same from the empty board.");
+        return;
+    };
+    println!("The strategies part at move {}:", divergence.line.len() + 1);
+    for (state, mov) in &divergence.line {
+        println!("  {}", state.strategy_line(*mov));
+    }
+    for (side, mov) in [("<", divergence.left), (">", divergence.right)] {
+        match mov {
+            Some(mov) => println!("{} {}", side, divergence.state.strategy_line(mov)),
+            None => println!("{} {} has no move", side, divergence.state.to_string()),
+        }
+    }
+}
+
+fn lookup(path: &str, state: &str) {
+    let Some(state) = StrategyState::from_string(state) else {
+        eprintln!("{state} is not a state, write it like A[BCabAcDaC]dc.");
+        std::process::exit(2);
+    };
+    match read(path).lookup(&state) {
+        Some(mov) => println!("{}", state.strategy_line(mov)),
+        None => println!("{} is not in the strategy.", state.to_string()),
+    }
+}
+
+fn main() {
+    match Args::parse().command {
+        Command::Merge { files, output } => merge(&files, &output),
+        Command::Diff { left, right } => diff(&left, &right),
+        Command::Lookup { file, state } => lookup(&file, &state),
+    }
+}
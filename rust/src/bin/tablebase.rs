@@ -0,0 +1,45 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::time::Instant;
+
+use clap::*;
+use segment_colouring::linear_axis::game::parse_max_events;
+use segment_colouring::linear_axis::tablebase::{Tablebase, Verdict};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "Tablebase",
+    about = "Solves every position of the segment colouring game up to <MAX_EVENTS> events and stores the verdicts."
+)]
+struct Args {
+    desired_number_of_colours: usize,
+    max_clicque: usize,
+    #[arg(value_parser = parse_max_events)]
+    max_events: usize,
+    /// Where to write the tablebase.
+    #[arg(long, default_value = "./rust.tablebase")]
+    output: String,
+}
+
+fn main() {
+    let args = Args::parse();
+    let start = Instant::now();
+    let tablebase = Tablebase::build(
+        args.max_clicque,
+        args.desired_number_of_colours,
+        args.max_events,
+    );
+    let wins = tablebase
+        .entries
+        .values()
+        .filter(|v| matches!(v, Verdict::Win { .. }))
+        .count();
+    println!(
+        "Solved {} states ({} won) in {:?}.",
+        tablebase.entries.len(),
+        wins,
+        start.elapsed()
+    );
+    let mut file = BufWriter::new(File::create(&args.output).unwrap());
+    tablebase.write(&mut file).unwrap();
+}
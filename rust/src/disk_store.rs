@@ -0,0 +1,400 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    fs::{self, File},
+    hash::{BuildHasher, Hash},
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{hash::GlobalRandomState, table::StateStore};
+
+/// A value of a fixed size on disk.
+pub trait Record: Copy {
+    const SIZE: usize;
+    fn write(&self, bytes: &mut [u8]);
+    fn read(bytes: &[u8]) -> Self;
+}
+
+/// Records read at once when looking a key up.
+const BLOCK_RECORDS: usize = 64;
+/// Runs kept on disk before they are merged into one.
+const MAX_RUNS: usize = 8;
+const BLOOM_BITS_PER_RECORD: usize = 10;
+const BLOOM_HASHES: u64 = 4;
+
+/// Records sorted by the hash of their key, written out from the memtable or merged from other runs.
+/// Only the first hash of every block and a bloom filter stay in memory.
+#[derive(Debug)]
+struct Run {
+    file: File,
+    path: PathBuf,
+    records: usize,
+    index: Vec<u64>,
+    bloom: Vec<u64>,
+}
+
+impl Run {
+    fn bloom_bits(hash: u64, bits: usize) -> impl Iterator<Item = usize> {
+        let step = hash.rotate_left(32) | 1;
+        (0..BLOOM_HASHES)
+            .map(move |i| (hash.wrapping_add(i.wrapping_mul(step)) % bits as u64) as usize)
+    }
+    fn may_contain(&self, hash: u64) -> bool {
+        let bits = self.bloom.len() * 64;
+        Self::bloom_bits(hash, bits).all(|bit| self.bloom[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+    /// Reads the records of block `b`, `bytes` being as long as they are.
+    fn read_block(&self, b: usize, record_size: usize, bytes: &mut [u8]) {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start((b * BLOCK_RECORDS * record_size) as u64))
+            .unwrap();
+        file.read_exact(bytes).unwrap();
+    }
+}
+
+/// A `StateStore` for tables larger than memory, organised like a log-structured merge tree.
+/// New entries go to an in-memory memtable, which is written out as a sorted run when it outgrows
+/// its share of `memory_bytes`. Lookups check the memtable and then the runs from the newest,
+/// reading one block of the run when its bloom filter lets the key through.
+/// Removals are recorded as tombstones until the runs are merged.
+/// Inserts do not look into the runs, so `len` counts a key written again after a flush twice
+/// until the runs are merged: it is exact only after `retain` and an upper bound otherwise.
+#[derive(Debug)]
+pub struct DiskStore<K, V, S = GlobalRandomState> {
+    dir: PathBuf,
+    memtable: HashMap<K, Option<V>, S>,
+    memtable_entries: usize,
+    /// The oldest first.
+    runs: Vec<Run>,
+    next_run: usize,
+    len: usize,
+    hasher: S,
+}
+
+impl<K, V, S> DiskStore<K, V, S>
+where
+    K: Record + Hash + Eq,
+    V: Record,
+    S: BuildHasher + Default,
+{
+    /// A store keeping its runs in `dir`, which is created if needed. The runs are deleted with the store.
+    pub fn new(dir: impl AsRef<Path>, memory_bytes: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        let entry = std::mem::size_of::<(K, Option<V>)>() + 1;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            memtable: HashMap::default(),
+            memtable_entries: (memory_bytes / 2 / entry).max(1),
+            runs: vec![],
+            next_run: 0,
+            len: 0,
+            hasher: S::default(),
+        })
+    }
+    /// How many sorted runs are on disk.
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+    fn record_size() -> usize {
+        8 + 1 + K::SIZE + V::SIZE
+    }
+    fn encode(&self, key: &K, value: Option<&V>, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        bytes.resize(start + Self::record_size(), 0);
+        let record = &mut bytes[start..];
+        record[..8].copy_from_slice(&self.hasher.hash_one(key).to_le_bytes());
+        key.write(&mut record[9..9 + K::SIZE]);
+        if let Some(value) = value {
+            record[8] = 1;
+            value.write(&mut record[9 + K::SIZE..]);
+        }
+    }
+    fn decode(record: &[u8]) -> (K, Option<V>) {
+        let key = K::read(&record[9..9 + K::SIZE]);
+        let value = (record[8] == 1).then(|| V::read(&record[9 + K::SIZE..]));
+        (key, value)
+    }
+    fn hash_of(record: &[u8]) -> u64 {
+        u64::from_le_bytes(record[..8].try_into().unwrap())
+    }
+    fn lookup(&self, key: &K) -> Option<Option<V>> {
+        if let Some(&value) = self.memtable.get(key) {
+            return Some(value);
+        }
+        let hash = self.hasher.hash_one(key);
+        let size = Self::record_size();
+        let mut block = vec![];
+        for run in self.runs.iter().rev() {
+            if !run.may_contain(hash) {
+                continue;
+            }
+            block.resize(BLOCK_RECORDS * size, 0);
+            // Records with the same hash may start at the end of the previous block.
+            let mut b = run
+                .index
+                .partition_point(|&first| first < hash)
+                .saturating_sub(1);
+            'blocks: while b < run.index.len() {
+                let records = BLOCK_RECORDS.min(run.records - b * BLOCK_RECORDS);
+                let bytes = &mut block[..records * size];
+                run.read_block(b, size, bytes);
+                for record in bytes.chunks(size) {
+                    match Self::hash_of(record).cmp(&hash) {
+                        std::cmp::Ordering::Less => {}
+                        std::cmp::Ordering::Greater => break 'blocks,
+                        std::cmp::Ordering::Equal => {
+                            let (found, value) = Self::decode(record);
+                            if found == *key {
+                                return Some(value);
+                            }
+                        }
+                    }
+                }
+                b += 1;
+            }
+        }
+        None
+    }
+    fn next_path(&mut self) -> PathBuf {
+        self.next_run += 1;
+        self.dir.join(format!("run-{}.bin", self.next_run))
+    }
+    /// Writes sorted records out as a run. Only their hashes are kept in memory meanwhile.
+    fn write_run(path: PathBuf, records: impl Iterator<Item = Vec<u8>>) -> io::Result<Option<Run>> {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        let mut writer = BufWriter::new(&file);
+        let mut hashes = vec![];
+        for record in records {
+            hashes.push(Self::hash_of(&record));
+            writer.write_all(&record)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        if hashes.is_empty() {
+            fs::remove_file(&path)?;
+            return Ok(None);
+        }
+        let bits = (hashes.len() * BLOOM_BITS_PER_RECORD).div_ceil(64) * 64;
+        let mut bloom = vec![0u64; bits / 64];
+        for &hash in &hashes {
+            for bit in Run::bloom_bits(hash, bits) {
+                bloom[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        Ok(Some(Run {
+            file,
+            path,
+            records: hashes.len(),
+            index: hashes.iter().step_by(BLOCK_RECORDS).copied().collect(),
+            bloom,
+        }))
+    }
+    /// The memtable as records sorted like a run.
+    fn memtable_records(&self) -> Vec<Vec<u8>> {
+        let mut records = self
+            .memtable
+            .iter()
+            .map(|(key, value)| {
+                let mut record = vec![];
+                self.encode(key, value.as_ref(), &mut record);
+                record
+            })
+            .collect::<Vec<_>>();
+        records.sort_unstable_by_key(|record| Self::hash_of(record));
+        records
+    }
+    fn run_records(run: &Run) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let size = Self::record_size();
+        (0..run.index.len()).flat_map(move |b| {
+            let records = BLOCK_RECORDS.min(run.records - b * BLOCK_RECORDS);
+            let mut block = vec![0; records * size];
+            run.read_block(b, size, &mut block);
+            block.chunks(size).map(<[u8]>::to_vec).collect::<Vec<_>>()
+        })
+    }
+    /// Every live record of the runs and the memtable, in hash order.
+    fn merged(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let mut sources = self
+            .runs
+            .iter()
+            .map(|run| Box::new(Self::run_records(run)) as Box<dyn Iterator<Item = Vec<u8>>>)
+            .chain([Box::new(self.memtable_records().into_iter()) as Box<dyn Iterator<Item = _>>])
+            .map(Iterator::peekable)
+            .collect::<Vec<_>>();
+        std::iter::from_fn(move || loop {
+            let hash = sources
+                .iter_mut()
+                .filter_map(|source| source.peek().map(|record| Self::hash_of(record)))
+                .min()?;
+            // The newest record of every key with this hash, the sources going from the oldest.
+            let mut group: Vec<Vec<u8>> = vec![];
+            for source in &mut sources {
+                while let Some(record) = source.next_if(|record| Self::hash_of(record) == hash) {
+                    let key = 9..9 + K::SIZE;
+                    match group
+                        .iter_mut()
+                        .find(|r| r[key.clone()] == record[key.clone()])
+                    {
+                        Some(older) => *older = record,
+                        None => group.push(record),
+                    }
+                }
+            }
+            group.retain(|record| record[8] == 1);
+            if !group.is_empty() {
+                return Some(group);
+            }
+        })
+        .flatten()
+    }
+    fn flush(&mut self) {
+        let path = self.next_path();
+        if let Some(run) = Self::write_run(path, self.memtable_records().into_iter()).unwrap() {
+            self.runs.push(run);
+        }
+        self.memtable.clear();
+        if self.runs.len() > MAX_RUNS {
+            self.compact(&mut |_, _| true);
+        }
+    }
+    /// Merges the memtable and all runs into one run, keeping the entries `keep` agrees to.
+    fn compact(&mut self, keep: &mut dyn FnMut(&K, &V) -> bool) {
+        let path = self.next_path();
+        let mut len = 0;
+        let records = self
+            .merged()
+            .filter(|record| {
+                let (key, value) = Self::decode(record);
+                keep(&key, &value.unwrap())
+            })
+            .inspect(|_| len += 1);
+        let run = Self::write_run(path, records).unwrap();
+        let old = std::mem::replace(&mut self.runs, run.into_iter().collect());
+        self.memtable.clear();
+        self.len = len;
+        // The store is whole again, a run left behind only takes up space.
+        for run in old {
+            let _ = fs::remove_file(&run.path);
+        }
+    }
+}
+
+impl<K, V, S> StateStore<K, V> for DiskStore<K, V, S>
+where
+    K: Record + Hash + Eq + Debug,
+    V: Record + Debug,
+    S: BuildHasher + Default + Debug,
+{
+    fn get(&self, key: &K) -> Option<V> {
+        self.lookup(key).flatten()
+    }
+    fn insert_with_work(&mut self, key: K, value: V, _work: usize) {
+        if !matches!(self.memtable.insert(key, Some(value)), Some(Some(_))) {
+            self.len += 1;
+        }
+        if self.memtable.len() >= self.memtable_entries {
+            self.flush();
+        }
+    }
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.get(key);
+        if value.is_some() {
+            self.len -= 1;
+            if self.runs.is_empty() {
+                self.memtable.remove(key);
+            } else {
+                self.memtable.insert(*key, None);
+            }
+        }
+        value
+    }
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn bytes(&self) -> usize {
+        self.memtable.capacity() * (std::mem::size_of::<(K, Option<V>)>() + 1)
+            + self
+                .runs
+                .iter()
+                .map(|run| (run.index.len() + run.bloom.len()) * 8)
+                .sum::<usize>()
+    }
+    fn for_each(&self, f: &mut dyn FnMut(&K, &V)) {
+        for record in self.merged() {
+            let (key, value) = Self::decode(&record);
+            f(&key, &value.unwrap());
+        }
+    }
+    fn retain(&mut self, keep: &mut dyn FnMut(&K, &V) -> bool) {
+        self.compact(keep);
+    }
+}
+
+impl<K, V, S> Drop for DiskStore<K, V, S> {
+    fn drop(&mut self) {
+        for run in &self.runs {
+            let _ = fs::remove_file(&run.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    impl Record for u32 {
+        const SIZE: usize = 4;
+        fn write(&self, bytes: &mut [u8]) {
+            bytes.copy_from_slice(&self.to_le_bytes());
+        }
+        fn read(bytes: &[u8]) -> Self {
+            u32::from_le_bytes(bytes.try_into().unwrap())
+        }
+    }
+
+    #[test]
+    fn test_disk_store() {
+        let dir = std::env::temp_dir().join(format!("disk-store-{}", std::process::id()));
+        let mut store = DiskStore::<u32, u32>::new(&dir, 1 << 10).unwrap();
+        let mut expected = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(47);
+        for i in 0..20_000 {
+            let key = rng.random_range(0..5000);
+            if rng.random_bool(0.2) {
+                assert_eq!(store.remove(&key), expected.remove(&key));
+            } else {
+                store.insert(key, i);
+                expected.insert(key, i);
+            }
+            assert!(store.len() >= expected.len());
+        }
+        assert!(store.runs() > 1 && store.runs() <= MAX_RUNS);
+        for key in 0..5000 {
+            assert_eq!(store.get(&key), expected.get(&key).copied());
+        }
+        let mut all = HashMap::new();
+        store.for_each(&mut |&key, &value| assert!(all.insert(key, value).is_none()));
+        assert_eq!(all, expected);
+
+        store.retain(&mut |_, value| value % 2 == 0);
+        expected.retain(|_, value| *value % 2 == 0);
+        assert_eq!(store.len(), expected.len());
+        assert_eq!(store.runs(), 1);
+        for key in 0..5000 {
+            assert_eq!(store.get(&key), expected.get(&key).copied());
+        }
+        drop(store);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+}
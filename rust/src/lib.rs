@@ -1,5 +1,10 @@
+pub mod disk_store;
+pub mod limits;
 pub mod linear_axis;
+pub mod presentation;
+pub mod progress;
 pub mod simple_state;
+pub mod table;
 mod utils;
 
 #[cfg(test)]
@@ -0,0 +1,101 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Where a search gives up. Unset limits never stop it.
+#[derive(Debug, Default, Clone)]
+pub struct SearchLimits {
+    pub time: Option<Duration>,
+    /// Positions expanded. Resuming keeps only the wins, so it makes progress only while every call settles some.
+    pub nodes: Option<usize>,
+    /// An estimate of the memory taken by the table of positions.
+    pub table_bytes: Option<usize>,
+    /// Set from elsewhere, e.g. a signal handler, to stop the search as soon as possible.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+/// How a search with `SearchLimits` ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOutcome {
+    Win,
+    /// No win was found. A position met again while it is still being searched counts as lost,
+    /// so a loss may come from the order the moves are tried in and is not a proof: another `MoveOrdering` may win.
+    Loss,
+    /// A limit was hit first. The wins found are kept, so searching again picks up from there.
+    Unknown,
+}
+
+/// What a search has used up of its `SearchLimits` so far.
+#[derive(Debug, Default, Clone)]
+pub struct Budget {
+    started: Option<Instant>,
+    nodes: usize,
+    exhausted: bool,
+}
+
+impl Budget {
+    /// Counts an expanded position, returns whether the search has to stop.
+    pub fn spend(&mut self, limits: &SearchLimits, table_bytes: usize) -> bool {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        self.nodes += 1;
+        self.exhausted |= limits.nodes.is_some_and(|nodes| self.nodes > nodes)
+            || limits.table_bytes.is_some_and(|bytes| table_bytes > bytes)
+            || limits.time.is_some_and(|time| started.elapsed() > time)
+            || limits
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+        self.exhausted
+    }
+    pub fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+    pub fn outcome(&self, won: bool) -> SearchOutcome {
+        match (won, self.exhausted) {
+            (true, _) => SearchOutcome::Win,
+            (false, false) => SearchOutcome::Loss,
+            (false, true) => SearchOutcome::Unknown,
+        }
+    }
+}
+
+#[test]
+fn test_budget() {
+    let limits = SearchLimits {
+        nodes: Some(2),
+        table_bytes: Some(100),
+        ..Default::default()
+    };
+    let mut budget = Budget::default();
+    assert!(!budget.spend(&limits, 10));
+    assert!(!budget.spend(&limits, 10));
+    assert_eq!(budget.outcome(false), SearchOutcome::Loss);
+    assert!(budget.spend(&limits, 10));
+    assert_eq!(budget.outcome(false), SearchOutcome::Unknown);
+    assert_eq!(budget.outcome(true), SearchOutcome::Win);
+
+    let mut budget = Budget::default();
+    assert!(budget.spend(&limits, 101));
+    let mut budget = Budget::default();
+    let limits = SearchLimits {
+        time: Some(Duration::ZERO),
+        ..Default::default()
+    };
+    std::thread::sleep(Duration::from_millis(1));
+    budget.spend(&limits, 0);
+    assert!(budget.spend(&limits, 0));
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let limits = SearchLimits {
+        cancel: Some(cancel.clone()),
+        ..Default::default()
+    };
+    let mut budget = Budget::default();
+    assert!(!budget.spend(&limits, 0));
+    cancel.store(true, Ordering::Relaxed);
+    assert!(budget.spend(&limits, 0));
+}
@@ -0,0 +1,566 @@
+use super::{
+    clicqued::recolourings,
+    event::{mirrored, Event},
+    game::GameAxis,
+    normalization::{NormalizedState, StrategyNormalizer},
+    recolour_segment,
+    strategy::{StrategyMove, StrategyState},
+    History, Queue,
+};
+
+// Events live on a circle, so there are no barriers to move.
+// Gap `i` is the place just before event `i`, gap `len` is the same place as gap `0`.
+// A segment (arc) inserted at `start_index` runs clockwise over the events
+// `start_index..end_index` taken modulo `len`, so `end_index` may exceed `len`.
+#[derive(Debug, Clone)]
+pub struct CircularAxis {
+    pub events: Queue<Event>,
+    pub max_clicque: usize,
+    pub intersections: Vec<usize>,
+    pub normalizer: StrategyNormalizer,
+}
+
+impl CircularAxis {
+    pub fn new(max_clicque: usize) -> Self {
+        Self::with_events(Queue::with_capacity(40), max_clicque)
+    }
+
+    pub fn with_events(events: Queue<Event>, max_clicque: usize) -> Self {
+        let mut result = Self {
+            events,
+            max_clicque,
+            intersections: vec![],
+            normalizer: StrategyNormalizer::new(),
+        };
+        result.count_intersections();
+        result
+    }
+
+    pub fn from_strategy_string(string: &str, max_clicque: usize) -> Self {
+        Self::with_events(
+            string
+                .trim_matches(['[', ']'])
+                .chars()
+                .map(Event::from_char)
+                .collect(),
+            max_clicque,
+        )
+    }
+
+    pub fn apply_history(&mut self, history: History) -> Option<History> {
+        let reverse = match history {
+            History::SegmentInsert {
+                start_index,
+                end_index,
+                color,
+            } => self.insert_segment(start_index, end_index, color),
+            History::SegmentRemove {
+                start_index,
+                end_index,
+            } => self.remove_segment(start_index, end_index),
+            History::SegmentDelete {
+                start_index,
+                end_index,
+            } => {
+                let color = self.events.get(start_index)?.colour();
+                self.remove_segment(start_index, end_index)
+                    .map(|_| History::SegmentRestore {
+                        start_index,
+                        end_index,
+                        color,
+                    })
+            }
+            History::SegmentRestore {
+                start_index,
+                end_index,
+                color,
+            } => self
+                .restore_segment(start_index, end_index, color)
+                .map(|()| History::SegmentDelete {
+                    start_index,
+                    end_index,
+                }),
+            History::SegmentRecolour {
+                start_index,
+                end_index,
+                color,
+            } => recolour_segment(&mut self.events, start_index, end_index, color).map(|c| {
+                History::SegmentRestoreColour {
+                    start_index,
+                    end_index,
+                    color: c,
+                }
+            }),
+            History::SegmentRestoreColour {
+                start_index,
+                end_index,
+                color,
+            } => recolour_segment(&mut self.events, start_index, end_index, color).map(|c| {
+                History::SegmentRecolour {
+                    start_index,
+                    end_index,
+                    color: c,
+                }
+            }),
+            _ => None,
+        };
+        self.count_intersections();
+        reverse
+    }
+
+    fn insert_segment(
+        &mut self,
+        start_index: usize,
+        end_index: usize,
+        color: u8,
+    ) -> Option<History> {
+        let len = self.events.len();
+        if start_index > len || end_index < start_index || end_index > start_index + len {
+            return None;
+        }
+        if end_index <= len {
+            self.events
+                .insert_at_index(end_index, Event::new_end(color))?;
+            self.events
+                .insert_at_index(start_index, Event::new_start(color))?;
+            Some(History::SegmentRemove {
+                start_index,
+                end_index: end_index + 1,
+            })
+        } else {
+            // The arc wraps around, its end lands before its start.
+            let wrapped_end = end_index - len;
+            self.events
+                .insert_at_index(wrapped_end, Event::new_end(color))?;
+            self.events
+                .insert_at_index(start_index + 1, Event::new_start(color))?;
+            Some(History::SegmentRemove {
+                start_index: start_index + 1,
+                end_index: wrapped_end,
+            })
+        }
+    }
+
+    /// Puts a deleted segment back, the endpoints land exactly at the given indices.
+    fn restore_segment(&mut self, start_index: usize, end_index: usize, color: u8) -> Option<()> {
+        let start = (start_index, Event::new_start(color));
+        let end = (end_index, Event::new_end(color));
+        let (first, second) = if start_index < end_index {
+            (start, end)
+        } else {
+            (end, start)
+        };
+        self.events.insert_at_index(first.0, first.1)?;
+        self.events.insert_at_index(second.0, second.1)
+    }
+
+    fn remove_segment(&mut self, start_index: usize, end_index: usize) -> Option<History> {
+        let (start, end) = (*self.events.get(start_index)?, *self.events.get(end_index)?);
+        if start.colour() != end.colour() || !start.is_start() || end.is_start() {
+            return None;
+        }
+        let (first, second) = if start_index < end_index {
+            (end_index, start_index)
+        } else {
+            (start_index, end_index)
+        };
+        self.events.remove_at_index(first)?;
+        self.events.remove_at_index(second)?;
+        let (start_index, end_index) = if start_index < end_index {
+            (start_index, end_index - 1)
+        } else {
+            (start_index - 1, end_index + self.events.len())
+        };
+        Some(History::SegmentInsert {
+            start_index,
+            end_index,
+            color: start.colour(),
+        })
+    }
+
+    fn count_intersections(&mut self) {
+        let mut current = self.segments_opened_at_zero();
+        let mut result = vec![];
+
+        for e in &self.events {
+            result.push(current);
+            if e.is_start() {
+                current += 1
+            } else {
+                current -= 1
+            }
+        }
+        result.push(current);
+        self.intersections = result;
+    }
+
+    /// Arcs passing through gap `0`, i.e. the ones whose end comes before their start.
+    pub fn segments_opened_at_zero(&self) -> usize {
+        let mut opened = 0u64;
+        let mut result = 0;
+        for e in &self.events {
+            if e.is_start() {
+                opened |= 1 << e.colour();
+            } else if opened & (1 << e.colour()) == 0 {
+                result += 1;
+            }
+        }
+        result
+    }
+
+    pub fn valid_new_segments(&self) -> Vec<(usize, usize)> {
+        let starts = self.events.len().max(1);
+        (0..starts)
+            .filter_map(|s| {
+                self.valid_new_segment_ends(s)
+                    .map(|(min_end, max_end)| (min_end..=max_end).map(move |e| (s, e)))
+            })
+            .flatten()
+            .collect()
+    }
+
+    pub fn valid_new_segment_ends(&self, start: usize) -> Option<(usize, usize)> {
+        let len = self.events.len();
+        let mut opened_before = self.intersections[start];
+        if opened_before >= self.max_clicque {
+            return None;
+        }
+        let event = |i: usize| self.events.get(i % len).unwrap();
+        let covered = |i: usize| self.intersections[i % len];
+        let mut i = start;
+        while opened_before != 0 {
+            if i == start + len || covered(i + 1) >= self.max_clicque {
+                return None;
+            }
+            if !event(i).is_start() {
+                opened_before -= 1
+            }
+            i += 1
+        }
+        let min_end = i;
+        while i < start + len {
+            if covered(i + 1) >= self.max_clicque || !event(i).is_start() {
+                break;
+            }
+            i += 1
+        }
+        Some((min_end, i))
+    }
+
+    pub fn segment_will_collide_with_colours(&self, start: usize, end: usize) -> Vec<bool> {
+        let mut collisions = vec![false; self.max_colors()];
+        let len = self.events.len();
+        for i in start..end {
+            let e = self.events.get(i % len).unwrap();
+            if !e.is_pending() {
+                collisions[e.colour() as usize] = true
+            }
+        }
+        collisions
+    }
+
+    pub fn uncollisions(&self, start: usize, end: usize) -> Vec<u8> {
+        self.segment_will_collide_with_colours(start, end)
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| if *c { None } else { Some(i as u8) })
+            .collect()
+    }
+
+    /// Every way of giving a single arc a different colour.
+    pub fn valid_recolourings(&self) -> Vec<History> {
+        let events = self.events.iter().copied().collect::<Vec<_>>();
+        let len = events.len();
+        let mut used = vec![false; self.max_colors()];
+        for e in events.iter().filter(|e| !e.is_pending()) {
+            used[e.colour() as usize] = true
+        }
+        let mut result = vec![];
+        for (start_index, start) in events.iter().enumerate() {
+            if !start.is_start() || start.is_pending() {
+                continue;
+            }
+            let Some(inner) = (1..len).find(|i| events[(start_index + i) % len] == start.sibling())
+            else {
+                continue;
+            };
+            let end_index = (start_index + inner) % len;
+            let collisions =
+                self.segment_will_collide_with_colours(start_index + 1, start_index + inner);
+            result.extend(
+                recolourings(&collisions, &used, start.colour()).map(|color| {
+                    History::SegmentRecolour {
+                        start_index,
+                        end_index,
+                        color,
+                    }
+                }),
+            );
+        }
+        result
+    }
+    /// Start and end indices of the segments Spoiler may delete, the end comes first for wrapping arcs.
+    pub fn removable_segments(&self) -> Vec<(usize, usize)> {
+        let events = self.events.iter().copied().collect::<Vec<_>>();
+        let len = events.len();
+        events
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.is_start() && !e.is_pending())
+            .filter_map(|(start_index, e)| {
+                let inner = (1..len).find(|i| events[(start_index + i) % len] == e.sibling())?;
+                Some((start_index, (start_index + inner) % len))
+            })
+            .collect()
+    }
+
+    pub fn colours_used(&self) -> usize {
+        let mut used = vec![false; self.max_colors()];
+        for e in self.events.iter().filter(|e| !e.is_pending()) {
+            used[e.colour() as usize] = true
+        }
+        used.into_iter().filter(|u| *u).count()
+    }
+
+    pub fn max_colors(&self) -> usize {
+        self.max_clicque * 2 - 1
+    }
+
+    /// Normalizes every rotation of the circle and keeps the greatest one.
+    /// Returns it together with the number of events rotated from the front to the back.
+    pub fn strategy_normalize_rotation(&mut self) -> (NormalizedState, usize) {
+        let events = self.events.iter().copied().collect::<Vec<_>>();
+        let colors = self.max_colors();
+        rotation_normalize(&mut self.normalizer, &events, colors)
+    }
+
+    fn rotate_move(&mut self, mov: StrategyMove, rotation: usize) -> StrategyMove {
+        let mut rotated_events = self.events.iter().copied().collect::<Vec<_>>();
+        rotated_events.rotate_left(rotation);
+        let colors = self.max_colors();
+        self.normalizer
+            .strategy_normalize_without_symmetry(&rotated_events, colors);
+        let mov = mov.with_list(|list| self.normalizer.normalize_colours(list, colors));
+        match mov {
+            StrategyMove::Insert { start, end }
+            | StrategyMove::InsertWithList { start, end, .. }
+            | StrategyMove::InsertWithDemand { start, end, .. } => {
+                let len = self.events.len().max(1);
+                let rotated = (start + len - rotation) % len;
+                mov.with_segment(rotated, rotated + end - start)
+            }
+            StrategyMove::Remove { start, end } => {
+                let len = self.events.len();
+                StrategyMove::Remove {
+                    start: (start + len - rotation) % len,
+                    end: (end + len - rotation) % len,
+                }
+            }
+            limit => limit,
+        }
+    }
+}
+
+fn rotation_normalize(
+    normalizer: &mut StrategyNormalizer,
+    events: &[Event],
+    max_colours: usize,
+) -> (NormalizedState, usize) {
+    let mut best = (
+        normalizer.strategy_normalize_without_symmetry(events, max_colours),
+        0,
+    );
+    let mut rotated = events.to_vec();
+    for rotation in 1..events.len() {
+        rotated.rotate_left(1);
+        let candidate = normalizer.strategy_normalize_without_symmetry(&rotated, max_colours);
+        if candidate > best.0 {
+            best = (candidate, rotation);
+        }
+    }
+    best
+}
+
+impl GameAxis for CircularAxis {
+    fn apply_history(&mut self, history: History) -> Option<History> {
+        self.apply_history(history)
+    }
+    fn colours_used(&self) -> usize {
+        self.colours_used()
+    }
+    fn max_colors(&self) -> usize {
+        self.max_colors()
+    }
+    fn max_clicque(&self) -> usize {
+        self.max_clicque
+    }
+    fn events_len(&self) -> usize {
+        self.events.len()
+    }
+    fn events(&self) -> Vec<Event> {
+        self.events.iter().copied().collect()
+    }
+    fn valid_new_segments(&self) -> Vec<(usize, usize)> {
+        self.valid_new_segments()
+    }
+    fn uncollisions(&self, start: usize, end: usize) -> Vec<u8> {
+        self.uncollisions(start, end)
+    }
+    fn segment_uncollisions(&self, start_index: usize, end_index: usize) -> Vec<u8> {
+        if start_index < end_index {
+            self.uncollisions(start_index + 1, end_index)
+        } else {
+            self.uncollisions(start_index + 1, end_index + self.events.len())
+        }
+    }
+    fn valid_recolourings(&self) -> Vec<History> {
+        self.valid_recolourings()
+    }
+    fn removable_segments(&self) -> Vec<(usize, usize)> {
+        self.removable_segments()
+    }
+    fn reductions(&self) -> &'static [History] {
+        &[]
+    }
+    fn normalize(&mut self) -> NormalizedState {
+        self.strategy_normalize_rotation().0
+    }
+    fn flipped(&mut self, state: &NormalizedState) -> NormalizedState {
+        let flipped = mirrored(state.0.iter());
+        let colors = self.max_colors();
+        rotation_normalize(&mut self.normalizer, &flipped, colors).0
+    }
+    fn normalize_move(&mut self, mov: StrategyMove) -> StrategyMove {
+        let (_, rotation) = self.strategy_normalize_rotation();
+        self.rotate_move(mov, rotation)
+    }
+    fn with_normalized(&self, state: &NormalizedState) -> Self {
+        Self::with_events(state.0.iter().copied().collect(), self.max_clicque)
+    }
+    fn strategy_state(&self, state: &NormalizedState) -> StrategyState {
+        StrategyState::circular(state)
+    }
+}
+
+#[test]
+fn test_circular_axis_history() {
+    let mut axis = CircularAxis::new(3);
+    let moves = vec![
+        History::SegmentInsert {
+            start_index: 0,
+            end_index: 0,
+            color: 0,
+        },
+        History::SegmentInsert {
+            start_index: 1,
+            end_index: 2,
+            color: 1,
+        },
+        // Wraps around: starts between `B` and `a`, ends between `A` and `B`.
+        History::SegmentInsert {
+            start_index: 3,
+            end_index: 5,
+            color: 2,
+        },
+    ];
+    let mut history = vec![];
+    for m in &moves {
+        history.push(axis.apply_history(*m).unwrap());
+    }
+    assert_eq!(
+        axis.events.iter().map(Event::to_char).collect::<String>(),
+        "AcBaCb"
+    );
+    assert_eq!(axis.intersections, vec![1, 2, 1, 2, 1, 2, 1]);
+    let mut reconstruct = vec![];
+    for h in history.iter().rev() {
+        reconstruct.push(axis.apply_history(*h).unwrap());
+    }
+    assert_eq!(moves, reconstruct.into_iter().rev().collect::<Vec<_>>());
+    assert!(axis.events.is_empty());
+}
+
+#[test]
+fn test_circular_axis_valid_segments() {
+    let axis = CircularAxis::from_strategy_string("[AaBb]", 2);
+    assert_eq!(
+        axis.valid_new_segments(),
+        vec![
+            (0, 0),
+            (0, 1),
+            (1, 2),
+            (1, 3),
+            (2, 2),
+            (2, 3),
+            (3, 4),
+            (3, 5)
+        ]
+    );
+    assert_eq!(axis.uncollisions(3, 5), vec![2]);
+    let axis = CircularAxis::from_strategy_string("[AcBaCb]", 2);
+    assert!(axis.valid_new_segments().is_empty());
+}
+
+#[test]
+fn test_circular_axis_rotation() {
+    let mut axis = CircularAxis::from_strategy_string("[AaBCbc]", 3);
+    let mut rotated = CircularAxis::from_strategy_string("[BCbcAa]", 3);
+    assert_eq!(
+        GameAxis::normalize(&mut axis),
+        GameAxis::normalize(&mut rotated)
+    );
+    let mov = GameAxis::normalize_move(&mut rotated, StrategyMove::Insert { start: 5, end: 8 });
+    let normalized = GameAxis::normalize(&mut rotated);
+    let mut rebuilt = GameAxis::with_normalized(&rotated, &normalized);
+    let StrategyMove::Insert { start, end } = mov else {
+        panic!()
+    };
+    assert_eq!(
+        rebuilt.uncollisions(start, end).len(),
+        rotated.uncollisions(5, 8).len()
+    );
+    assert_eq!(GameAxis::normalize(&mut rebuilt), normalized);
+}
+
+#[test]
+fn test_circular_game() {
+    use super::game::Game;
+    let mut game = Game::with_axis(CircularAxis::new(2), 6, 3, None);
+    assert!(game.simulate(-1));
+    let mut game = Game::with_axis(CircularAxis::new(2), 6, 4, None);
+    assert!(!game.simulate(-1));
+    let mut game = Game::with_axis(CircularAxis::new(3), 10, 5, None);
+    game.deletions = true;
+    assert!(game.simulate(-1));
+}
+
+#[test]
+fn test_circular_delete() {
+    let mut axis = CircularAxis::from_strategy_string("[AcBaCb]", 3);
+    assert_eq!(axis.removable_segments(), vec![(0, 3), (2, 5), (4, 1)]);
+    let reverse = axis
+        .apply_history(History::SegmentDelete {
+            start_index: 4,
+            end_index: 1,
+        })
+        .unwrap();
+    assert_eq!(
+        axis.events.iter().map(Event::to_char).collect::<String>(),
+        "ABab"
+    );
+    axis.apply_history(reverse).unwrap();
+    assert_eq!(
+        axis.events.iter().map(Event::to_char).collect::<String>(),
+        "AcBaCb"
+    );
+    // Endpoints of different segments are left on the board.
+    let delete = History::SegmentDelete {
+        start_index: 0,
+        end_index: 1,
+    };
+    assert_eq!(axis.apply_history(delete), None);
+    assert_eq!(
+        axis.events.iter().map(Event::to_char).collect::<String>(),
+        "AcBaCb"
+    );
+}
@@ -1,6 +1,7 @@
 use futures::future::join_all;
 
 use super::{
+    event::Event,
     normalization::{NormalizedState, StrategyNormalizer},
     History, LinearAxis,
 };
@@ -11,6 +12,9 @@ pub struct ClicquedLinearAxis {
     pub max_clicque: usize,
     pub intersections: Vec<usize>,
     pub normalizer: StrategyNormalizer,
+    /// How many events of each colour are on the board.
+    colour_events: [u8; 64],
+    colours_used: usize,
 }
 
 impl ClicquedLinearAxis {
@@ -24,15 +28,165 @@ impl ClicquedLinearAxis {
             max_clicque,
             intersections: vec![],
             normalizer: StrategyNormalizer::new(),
+            colour_events: [0; 64],
+            colours_used: 0,
         };
-        result.count_intersections();
+        result.intersections = result.counted_intersections();
+        let events = result.inner.events.iter().copied().collect::<Vec<_>>();
+        result.count_colours(events, true);
         result
     }
 
+    /// Applies the move, updating the intersections and the colours used for just the part of the board it touched.
     pub fn apply_history(&mut self, history: History) -> Option<History> {
-        let reverse = self.inner.apply_history(history, self.max_colors());
-        self.count_intersections();
-        reverse
+        let reverse = self.inner.apply_history(history, self.max_colors())?;
+        match (history, reverse) {
+            (
+                History::SegmentInsert {
+                    start_index,
+                    end_index,
+                    color,
+                },
+                _,
+            )
+            | (
+                History::SegmentRestore {
+                    start_index, color, ..
+                },
+                History::SegmentDelete { end_index, .. },
+            ) => {
+                let end_index = match history {
+                    History::SegmentRestore { .. } => end_index - 1,
+                    _ => end_index,
+                };
+                self.intersections
+                    .insert(end_index + 1, self.intersections[end_index]);
+                self.intersections
+                    .insert(start_index, self.intersections[start_index]);
+                for i in start_index + 1..=end_index + 1 {
+                    self.intersections[i] += 1;
+                }
+                self.count_colours([Event::new_start(color); 2], true);
+            }
+            (
+                History::SegmentRemove {
+                    start_index,
+                    end_index,
+                }
+                | History::SegmentDelete {
+                    start_index,
+                    end_index,
+                },
+                History::SegmentInsert { color, .. } | History::SegmentRestore { color, .. },
+            ) => {
+                self.intersections.remove(end_index + 1);
+                for i in start_index + 1..=end_index {
+                    self.intersections[i] -= 1;
+                }
+                self.intersections.remove(start_index);
+                self.count_colours([Event::new_start(color); 2], false);
+            }
+            (History::LimitFront, History::EventInsertFront { end, lost }) => {
+                self.intersections.drain(..=lost);
+                self.shift_intersections();
+                let lost = self.inner.front.iter().rev().take(lost).copied();
+                self.count_colours(lost.chain([end]).collect::<Vec<_>>(), false);
+            }
+            (History::LimitBack, History::EventInsertBack { start, lost }) => {
+                let len = self.intersections.len();
+                self.intersections.truncate(len - lost - 1);
+                self.shift_intersections();
+                let lost = self.inner.back.iter().take(lost).copied();
+                self.count_colours(lost.chain([start]).collect::<Vec<_>>(), false);
+            }
+            (History::EventInsertFront { lost, .. }, _) => {
+                let restored = self.inner.events.iter().take(lost + 1).copied();
+                let restored = restored.collect::<Vec<_>>();
+                // Counted back from the old front, `shift_intersections` fixes the offset.
+                let mut current = self.intersections[0];
+                let mut before = restored
+                    .iter()
+                    .rev()
+                    .map(|e| {
+                        current = match e.is_start() {
+                            true => current.wrapping_sub(1),
+                            false => current.wrapping_add(1),
+                        };
+                        current
+                    })
+                    .collect::<Vec<_>>();
+                before.reverse();
+                self.intersections.splice(0..0, before);
+                self.shift_intersections();
+                self.count_colours(restored, true);
+            }
+            (History::EventInsertBack { lost, .. }, _) => {
+                let restored = self.inner.events.iter().rev().take(lost + 1).copied();
+                let mut restored = restored.collect::<Vec<_>>();
+                restored.reverse();
+                let mut current = *self.intersections.last().unwrap();
+                for e in &restored {
+                    current = match e.is_start() {
+                        true => current.wrapping_add(1),
+                        false => current.wrapping_sub(1),
+                    };
+                    self.intersections.push(current);
+                }
+                self.shift_intersections();
+                self.count_colours(restored, true);
+            }
+            (
+                History::SegmentRecolour { color, .. }
+                | History::SegmentRestoreColour { color, .. },
+                History::SegmentRecolour { color: old, .. }
+                | History::SegmentRestoreColour { color: old, .. },
+            ) => {
+                self.count_colours([Event::new_start(old); 2], false);
+                self.count_colours([Event::new_start(color); 2], true);
+            }
+            (History::SegmentTie { .. } | History::SegmentUntie { .. }, _) => {}
+            _ => unreachable!("{history:?} undone by {reverse:?}"),
+        }
+        debug_assert_eq!(
+            self.intersections,
+            self.counted_intersections(),
+            "{history:?} {reverse:?} {}",
+            self.inner.to_string()
+        );
+        debug_assert_eq!(
+            self.colours_used,
+            self.counted_colours_used(),
+            "after {history:?}"
+        );
+        Some(reverse)
+    }
+
+    /// Moving a barrier can change how many segments count as opened before the board,
+    /// which shifts every intersection alike.
+    fn shift_intersections(&mut self) {
+        let shift = self
+            .segments_opened_at_front()
+            .wrapping_sub(self.intersections[0]);
+        for i in &mut self.intersections {
+            *i = i.wrapping_add(shift);
+        }
+    }
+
+    fn count_colours(&mut self, events: impl IntoIterator<Item = Event>, added: bool) {
+        for e in events.into_iter().filter(|e| !e.is_pending()) {
+            let count = &mut self.colour_events[e.colour() as usize];
+            if added {
+                *count += 1;
+                if *count == 1 {
+                    self.colours_used += 1;
+                }
+            } else {
+                *count -= 1;
+                if *count == 0 {
+                    self.colours_used -= 1;
+                }
+            }
+        }
     }
     pub async fn generate_all_states_async(
         &mut self,
@@ -146,7 +300,7 @@ impl ClicquedLinearAxis {
         false
     }
 
-    fn count_intersections(&mut self) {
+    fn counted_intersections(&self) -> Vec<usize> {
         let mut current = self.segments_opened_at_front();
         let mut result = vec![];
 
@@ -159,16 +313,16 @@ impl ClicquedLinearAxis {
             }
         }
         result.push(current);
-        self.intersections = result;
+        result
     }
 
     pub fn segments_opened_at_front(&self) -> usize {
-        let mut opened = vec![false; self.max_colors()];
+        let mut opened = 0u64;
         let mut result = 0;
         for e in &self.inner.events {
             if e.is_start() {
-                opened[e.colour() as usize] = true;
-            } else if !opened[e.colour() as usize] {
+                opened |= 1 << e.colour();
+            } else if opened & (1 << e.colour()) == 0 {
                 result += 1;
             }
         }
@@ -182,9 +336,26 @@ impl ClicquedLinearAxis {
                     .map(|(min_end, max_end)| (min_end..=max_end).map(move |e| (s, e)))
             })
             .flatten()
+            .filter(|&(s, e)| !self.inside_tie(s) && !self.inside_tie(e))
             .collect()
     }
 
+    /// Segments demanding `demand` colours, inserted as that many tied copies.
+    /// The clicque is counted in total demand.
+    pub fn valid_new_segments_with_demand(&self, demand: usize) -> Vec<(usize, usize)> {
+        self.valid_new_segments()
+            .into_iter()
+            .filter(|&(s, e)| {
+                self.intersections[s..=e].iter().max().unwrap() + demand <= self.max_clicque
+            })
+            .collect()
+    }
+
+    /// Whether the gap before the event at `index` lies between two tied events.
+    fn inside_tie(&self, index: usize) -> bool {
+        index > 0 && self.inner.events.get(index - 1).is_some_and(Event::is_tied)
+    }
+
     pub fn valid_new_segment_ends(&self, start: usize) -> Option<(usize, usize)> {
         let mut opened_before = self.segments_opened_at_front();
         let evs = &self.inner.events;
@@ -228,18 +399,73 @@ impl ClicquedLinearAxis {
     pub fn segment_will_collide_with_colours(&self, start: usize, end: usize) -> Vec<bool> {
         let mut collisions = vec![false; self.max_colors()];
         for e in self.inner.events.iter().skip(start).take(end - start) {
-            collisions[e.colour() as usize] = true
+            if !e.is_pending() {
+                collisions[e.colour() as usize] = true
+            }
         }
         collisions
     }
 
+    /// Every way of giving a single segment lying entirely on the board a different colour.
+    /// Segments reaching past a barrier may collide with forgotten ones, so they keep their colours.
+    pub fn valid_recolourings(&self) -> Vec<History> {
+        let events = self.inner.events.iter().copied().collect::<Vec<_>>();
+        let mut used = vec![false; self.max_colors()];
+        for e in events.iter().filter(|e| !e.is_pending()) {
+            used[e.colour() as usize] = true
+        }
+        let mut result = vec![];
+        for (start_index, start) in events.iter().enumerate() {
+            if !start.is_start() || start.is_pending() {
+                continue;
+            }
+            let Some(end_index) =
+                (start_index + 1..events.len()).find(|&i| events[i] == start.sibling())
+            else {
+                continue;
+            };
+            let collisions = self.segment_will_collide_with_colours(start_index + 1, end_index);
+            result.extend(
+                recolourings(&collisions, &used, start.colour()).map(|color| {
+                    History::SegmentRecolour {
+                        start_index,
+                        end_index,
+                        color,
+                    }
+                }),
+            );
+        }
+        result
+    }
+
+    /// Start and end indices of the segments lying entirely on the board, which Spoiler may delete.
+    /// Tied copies only go together, so they are left alone.
+    pub fn removable_segments(&self) -> Vec<(usize, usize)> {
+        let events = self.inner.events.iter().copied().collect::<Vec<_>>();
+        let tied = |i: usize| events[i].is_tied() || self.inside_tie(i);
+        events
+            .iter()
+            .enumerate()
+            .filter(|&(i, e)| e.is_start() && !e.is_pending() && !tied(i))
+            .filter_map(|(start_index, e)| {
+                let end_index =
+                    (start_index + 1..events.len()).find(|&i| events[i] == e.sibling())?;
+                Some((start_index, end_index))
+            })
+            .filter(|&(_, end_index)| !tied(end_index))
+            .collect()
+    }
+
     fn valid_new_segment_starts<'a>(&'a self) -> impl Iterator<Item = usize> + use<'a> {
         (0..self.intersections.len())
             .filter(|&i| i == self.intersections.len() || self.intersections[i] < self.max_clicque)
     }
     pub fn colours_used(&self) -> usize {
+        self.colours_used
+    }
+    fn counted_colours_used(&self) -> usize {
         let mut used = vec![false; self.max_colors()];
-        for e in &self.inner.events {
+        for e in self.inner.events.iter().filter(|e| !e.is_pending()) {
             used[e.colour() as usize] = true
         }
         used.into_iter().filter(|u| *u).count()
@@ -256,6 +482,20 @@ impl ClicquedLinearAxis {
     }
 }
 
+/// Colours a segment may be switched to.
+/// Colours absent from the board are interchangeable, so only the first of them is offered.
+pub(super) fn recolourings<'a>(
+    collisions: &'a [bool],
+    used: &'a [bool],
+    current: u8,
+) -> impl Iterator<Item = u8> + 'a {
+    let first_unused = used.iter().position(|u| !u);
+    (0..collisions.len())
+        .filter(move |&c| !collisions[c] && c != current as usize)
+        .filter(move |&c| used[c] || Some(c) == first_unused)
+        .map(|c| c as u8)
+}
+
 #[test]
 fn test_clicqued_linear_axis() {
     let mut axis = ClicquedLinearAxis::new(3);
@@ -279,3 +519,97 @@ fn test_clicqued_linear_axis() {
     println!("{}", axis.inner.to_string());
     dbg!(axis.intersections);
 }
+
+#[test]
+fn test_valid_recolourings() {
+    let axis = ClicquedLinearAxis::from_strategy_string("A[BaCbDc]d", 3);
+    assert_eq!(
+        axis.valid_recolourings(),
+        vec![
+            History::SegmentRecolour {
+                start_index: 0,
+                end_index: 3,
+                color: 3,
+            },
+            History::SegmentRecolour {
+                start_index: 0,
+                end_index: 3,
+                color: 4,
+            },
+            History::SegmentRecolour {
+                start_index: 2,
+                end_index: 5,
+                color: 0,
+            },
+            History::SegmentRecolour {
+                start_index: 2,
+                end_index: 5,
+                color: 4,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_valid_segments_with_demand() {
+    let axis = ClicquedLinearAxis::from_strategy_string("[A=Ba=bCc]", 3);
+    assert_eq!(
+        axis.valid_new_segments(),
+        vec![
+            (0, 0),
+            (0, 2),
+            (2, 4),
+            (2, 5),
+            (4, 4),
+            (4, 5),
+            (5, 6),
+            (6, 6)
+        ]
+    );
+    assert_eq!(
+        axis.valid_new_segments_with_demand(2),
+        vec![(0, 0), (4, 4), (4, 5), (5, 6), (6, 6)]
+    );
+    assert_eq!(
+        axis.valid_new_segments_with_demand(3),
+        vec![(0, 0), (4, 4), (6, 6)]
+    );
+}
+
+#[test]
+fn test_removable_segments() {
+    let axis = ClicquedLinearAxis::from_strategy_string("A[BaCbD=Ecd=e]", 3);
+    assert_eq!(axis.removable_segments(), vec![(0, 3), (2, 6)]);
+}
+
+#[test]
+fn test_incremental_counts() {
+    let mut axis = ClicquedLinearAxis::from_strategy_string("[AaBCbDcd]", 3);
+    let moves = [
+        History::LimitBack,
+        History::LimitFront,
+        History::SegmentInsert {
+            start_index: 1,
+            end_index: 2,
+            color: 4,
+        },
+        History::SegmentRecolour {
+            start_index: 1,
+            end_index: 3,
+            color: 0,
+        },
+        History::LimitBack,
+    ];
+    let mut reverses = vec![];
+    for mv in moves {
+        reverses.push(axis.apply_history(mv).unwrap());
+        assert_eq!(axis.intersections, axis.counted_intersections());
+        assert_eq!(axis.colours_used(), axis.counted_colours_used());
+    }
+    assert_eq!(axis.colours_used(), 2);
+    for reverse in reverses.into_iter().rev() {
+        axis.apply_history(reverse).unwrap();
+    }
+    assert_eq!(axis.intersections, vec![0, 1, 0, 1, 2, 1, 2, 1, 0]);
+    assert_eq!(axis.colours_used(), 4);
+}
@@ -0,0 +1,65 @@
+use super::{
+    event::{mirrored, Event},
+    normalization::NormalizedState,
+};
+
+/// Whether Spoiler wins from `state` whenever it wins from `sub`.
+/// This holds when `sub` is, up to renaming the colours and the mirror, what is left of `state`
+/// after moving the barriers: Spoiler moves them there and follows the strategy from `sub`.
+/// Any other extra segment could take the room a later insertion needs, so nothing else is claimed.
+/// `Game::barrier_lookahead` prunes by it, looking for `sub` among the positions the barriers leave.
+pub fn dominates(state: &NormalizedState, sub: &NormalizedState) -> bool {
+    let flipped = mirrored(sub.0.iter());
+    reachable_windows(&state.0).any(|(front, back)| {
+        let window = &state.0[front..back];
+        same_up_to_colours(window, &sub.0) || same_up_to_colours(window, &flipped)
+    })
+}
+
+/// The parts of the board the barriers can leave: the front one cuts just after an end, the back one just before a start.
+fn reachable_windows(events: &[Event]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let fronts = (0..=events.len()).filter(|&i| i == 0 || !events[i - 1].is_start());
+    fronts.flat_map(move |front| {
+        (front..=events.len())
+            .filter(|&i| i == events.len() || events[i].is_start())
+            .map(move |back| (front, back))
+    })
+}
+
+fn same_up_to_colours(a: &[Event], b: &[Event]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut forward = [u8::MAX; 256];
+    let mut backward = [u8::MAX; 256];
+    a.iter().zip(b).all(|(e, f)| {
+        if e.is_start() != f.is_start() || e.is_tied() != f.is_tied() {
+            return false;
+        }
+        let (c, d) = (e.colour() as usize, f.colour() as usize);
+        if forward[c] == u8::MAX && backward[d] == u8::MAX {
+            forward[c] = d as u8;
+            backward[d] = c as u8;
+        }
+        forward[c] == d as u8 && backward[d] == c as u8
+    })
+}
+
+#[test]
+fn test_dominates() {
+    use super::event::events_from_string;
+
+    let state = |s: &str| NormalizedState(events_from_string(s));
+    assert!(dominates(&state("AaBCbc"), &state("BCbc")));
+    assert!(dominates(&state("AaBCbc"), &state("AaB")));
+    assert!(dominates(&state("ABaCbc"), &state("Cbc")));
+    // "ABabCc" is the mirror image of "AaBCbc".
+    assert!(dominates(&state("ABabCcDd"), &state("AaBCbc")));
+    assert!(!dominates(&state("ABabCcDd"), &state("ABaCbc")));
+    // The front barrier can't stop in the middle of the starts.
+    assert!(!dominates(&state("ABab"), &state("Bab")));
+    // Nor the back one between the ends.
+    assert!(!dominates(&state("ABab"), &state("ABa")));
+    // Same shape, but the colours don't match.
+    assert!(!dominates(&state("AaBb"), &state("AaAa")));
+}
@@ -4,6 +4,15 @@ use std::fmt::Debug;
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash, PartialOrd, Ord, Default)]
 pub struct Event(u8);
 
+/// Segments Spoiler has committed to but the Algorithm has not coloured yet
+/// carry the colours `PENDING_COLOUR + i`, `i` being their place in the queue.
+/// Normalization keeps these colours as they are.
+pub const PENDING_COLOUR: u8 = 32;
+
+/// Marks an event taking place at the same point as the next one.
+/// The copies of a segment demanding several colours are tied together this way.
+const TIED: u8 = 1 << 7;
+
 impl Event {
     pub fn new_start(color: u8) -> Self {
         Self::new(true, color)
@@ -12,7 +21,17 @@ impl Event {
         Self::new(false, color)
     }
     pub fn with_color(&self, color: u8) -> Self {
-        Self::new(self.is_start(), color)
+        Self::new(self.is_start(), color).tied(self.is_tied())
+    }
+    pub fn tied(&self, tied: bool) -> Self {
+        if tied {
+            Event(self.0 | TIED)
+        } else {
+            Event(self.0 & !TIED)
+        }
+    }
+    pub fn is_tied(&self) -> bool {
+        (self.0 & TIED) != 0
     }
     fn new(is_start: bool, color: u8) -> Self {
         Event((is_start as u8) | (color << 1))
@@ -21,13 +40,26 @@ impl Event {
         (self.0 & 1) != 0
     }
     pub fn colour(&self) -> u8 {
-        self.0 >> 1
+        (self.0 & !TIED) >> 1
+    }
+    pub fn is_pending(&self) -> bool {
+        self.colour() >= PENDING_COLOUR
     }
+    // Pending segments are written in Greek, so that they never clash with a colour: `Α`, `Β`, ...
     pub fn to_char(&self) -> char {
+        if self.is_pending() {
+            let case = if self.is_start() { 'Α' } else { 'α' };
+            return char::from_u32(case as u32 + (self.colour() - PENDING_COLOUR) as u32).unwrap();
+        }
         let case = if self.is_start() { 'A' } else { 'a' };
         (case as u8 + self.colour()) as char
     }
     pub fn from_char(c: char) -> Self {
+        for (case, is_start) in [('Α', true), ('α', false)] {
+            if let Some(i) = (c as u32).checked_sub(case as u32).filter(|&i| i < 25) {
+                return Self::new(is_start, PENDING_COLOUR + i as u8);
+            }
+        }
         if c >= 'a' {
             Self::new_end(c as u8 - 'a' as u8)
         } else {
@@ -37,6 +69,86 @@ impl Event {
     pub fn sibling(&self) -> Self {
         Event(self.0 ^ 1)
     }
+    /// The event as stored, for writing it out.
+    pub fn to_byte(&self) -> u8 {
+        self.0
+    }
+    pub fn from_byte(byte: u8) -> Self {
+        Event(byte)
+    }
+}
+
+/// Writes the events down, with a `=` between events tied together.
+pub fn events_to_string<'a>(events: impl IntoIterator<Item = &'a Event>) -> String {
+    let mut result = String::new();
+    for e in events {
+        result.push(e.to_char());
+        if e.is_tied() {
+            result.push('=');
+        }
+    }
+    result
+}
+
+pub fn events_from_string(string: &str) -> Vec<Event> {
+    let mut result: Vec<Event> = vec![];
+    for c in string.chars() {
+        if c == '=' {
+            let last = result.last_mut().unwrap();
+            *last = last.tied(true);
+        } else {
+            result.push(Event::from_char(c));
+        }
+    }
+    result
+}
+
+/// The events seen in a mirror, ties stay between the same pairs of events.
+pub fn mirrored<'a>(events: impl DoubleEndedIterator<Item = &'a Event>) -> Vec<Event> {
+    let reversed = events.rev().map(Event::sibling).collect::<Vec<_>>();
+    (0..reversed.len())
+        .map(|i| reversed[i].tied(i + 1 < reversed.len() && reversed[i + 1].is_tied()))
+        .collect()
+}
+
+/// Copies tied together are interchangeable, so each run of tied events gets its colours in order.
+pub fn sort_ties(events: &mut [Event]) {
+    let mut start = 0;
+    while start < events.len() {
+        let mut end = start;
+        while events[end].is_tied() && end + 1 < events.len() {
+            end += 1;
+        }
+        let mut colours = events[start..=end]
+            .iter()
+            .map(Event::colour)
+            .collect::<Vec<_>>();
+        colours.sort();
+        for (e, c) in events[start..=end].iter_mut().zip(colours) {
+            *e = e.with_color(c);
+        }
+        start = end + 1;
+    }
+}
+
+/// Start and end indices of the pending segments, in queue order.
+pub fn pending_segments(events: &[Event]) -> Vec<(usize, usize)> {
+    let mut result = vec![];
+    for (i, e) in events.iter().enumerate() {
+        if !e.is_pending() {
+            continue;
+        }
+        let pending = (e.colour() - PENDING_COLOUR) as usize;
+        if result.len() <= pending {
+            result.resize(pending + 1, (usize::MAX, usize::MAX));
+        }
+        if e.is_start() {
+            result[pending].0 = i
+        } else {
+            result[pending].1 = i
+        }
+    }
+    result
 }
 
 impl Debug for Event {
@@ -44,6 +156,40 @@ impl Debug for Event {
         f.debug_struct("Event")
             .field("is_start", &self.is_start())
             .field("color", &self.colour())
+            .field("tied", &self.is_tied())
             .finish()
     }
 }
+
+#[test]
+fn test_pending_chars() {
+    let events = "AΑaBαΒbβ".chars().map(Event::from_char).collect::<Vec<_>>();
+    assert_eq!(pending_segments(&events), vec![(1, 4), (5, 7)]);
+    assert_eq!(
+        events.iter().map(Event::to_char).collect::<String>(),
+        "AΑaBαΒbβ"
+    );
+    // Every colour of a large clicque reads back as itself.
+    for colour in 0..26 {
+        for event in [Event::new_start(colour), Event::new_end(colour)] {
+            assert!(!event.is_pending());
+            assert_eq!(Event::from_char(event.to_char()), event);
+        }
+    }
+}
+
+#[test]
+fn test_tied_events() {
+    let events = events_from_string("A=BCa=bc");
+    assert!(events[0].is_tied() && !events[1].is_tied());
+    assert_eq!(events[0].colour(), 0);
+    assert_eq!(events_to_string(&events), "A=BCa=bc");
+    assert_eq!(events_to_string(&mirrored(events.iter())), "CB=Acb=a");
+    assert_eq!(
+        events_to_string(&mirrored(mirrored(events.iter()).iter())),
+        "A=BCa=bc"
+    );
+    let mut events = events_from_string("C=AB=Dc=ab=d");
+    sort_ties(&mut events);
+    assert_eq!(events_to_string(&events), "A=CB=Da=cb=d");
+}
@@ -1,17 +1,178 @@
 use std::{
+    cmp::Reverse,
     collections::{HashMap, HashSet},
-    isize, u8,
+    io, isize,
+    path::Path,
+    u8,
 };
 
-use crate::{hash::GlobalRandomState, linear_axis::LinearAxis};
+use crate::{
+    disk_store::{DiskStore, Record},
+    hash::GlobalRandomState,
+    limits::{Budget, SearchLimits, SearchOutcome},
+    linear_axis::{dominance::dominates, tablebase::Verdict, LinearAxis},
+    presentation::Presentation,
+    progress::{Progress, ProgressReporter},
+    table::{Replaceable, StateStore, Table},
+};
 
 use super::{
     clicqued::ClicquedLinearAxis,
-    normalization::NormalizedState,
-    strategy::{StrategyConsumer, StrategyMove, StrategyState},
+    event::{pending_segments, Event, PENDING_COLOUR},
+    normalization::{NormalizationCache, NormalizedState, StateKey, MAX_KEY_EVENTS},
+    ordering::{MoveHistory, MoveOrdering},
+    strategy::{Strategy, StrategyConsumer, StrategyMove, StrategyState},
     History,
 };
 
+/// The most events a game may be confined to. Spoiler inserts into boards one event short of it,
+/// and the board that leaves has to fit in a `StateKey`.
+pub const MAX_EVENTS: usize = MAX_KEY_EVENTS - 1;
+
+/// Parses `max_events` on the command line, refusing more than `MAX_EVENTS`.
+pub fn parse_max_events(s: &str) -> Result<usize, String> {
+    let max_events = s.parse::<usize>().map_err(|e| e.to_string())?;
+    if max_events > MAX_EVENTS {
+        return Err(format!("at most {MAX_EVENTS} events are supported"));
+    }
+    Ok(max_events)
+}
+
+/// The board a `Game` is played on.
+/// The solver only talks to the board through this trait,
+/// so the same search runs on the linear axis and on its circular counterpart.
+pub trait GameAxis: Clone {
+    fn apply_history(&mut self, history: History) -> Option<History>;
+    fn colours_used(&self) -> usize;
+    fn max_colors(&self) -> usize;
+    fn max_clicque(&self) -> usize;
+    fn events_len(&self) -> usize;
+    fn events(&self) -> Vec<Event>;
+    fn valid_new_segments(&self) -> Vec<(usize, usize)>;
+    /// Where a segment demanding `demand` colours fits. Boards without ties only take single segments.
+    fn valid_new_segments_with_demand(&self, demand: usize) -> Vec<(usize, usize)> {
+        if demand == 1 {
+            self.valid_new_segments()
+        } else {
+            vec![]
+        }
+    }
+    fn uncollisions(&self, start: usize, end: usize) -> Vec<u8>;
+    /// Colours the segment with endpoints at `start_index` and `end_index` could take.
+    fn segment_uncollisions(&self, start_index: usize, end_index: usize) -> Vec<u8>;
+    /// Recolourings available to the Algorithm when it is allowed recourse.
+    fn valid_recolourings(&self) -> Vec<History>;
+    /// Segments Spoiler may delete in the dynamic game, as start and end indices.
+    fn removable_segments(&self) -> Vec<(usize, usize)>;
+    /// Moves that shrink the board, tried when it gets too large.
+    fn reductions(&self) -> &'static [History];
+    /// The key under which the current position is stored (mirror symmetry is not applied).
+    fn normalize(&mut self) -> NormalizedState;
+    fn flipped(&mut self, state: &NormalizedState) -> NormalizedState;
+    /// `normalize` packed into a key.
+    fn key(&mut self) -> StateKey {
+        StateKey::from(&self.normalize())
+    }
+    fn flipped_key(&mut self, key: &StateKey) -> StateKey {
+        StateKey::from(&self.flipped(&key.state()))
+    }
+    /// The board as it is, before normalization.
+    fn raw_key(&self) -> StateKey {
+        StateKey::from_events(self.events().iter())
+    }
+    /// Translates a move made on the current board to the frame of `normalize`.
+    fn normalize_move(&mut self, mov: StrategyMove) -> StrategyMove;
+    /// A board laid out exactly like the stored `state`.
+    fn with_normalized(&self, state: &NormalizedState) -> Self;
+    fn strategy_state(&self, state: &NormalizedState) -> StrategyState;
+}
+
+impl GameAxis for ClicquedLinearAxis {
+    fn apply_history(&mut self, history: History) -> Option<History> {
+        self.apply_history(history)
+    }
+    fn colours_used(&self) -> usize {
+        self.colours_used()
+    }
+    fn max_colors(&self) -> usize {
+        self.max_colors()
+    }
+    fn max_clicque(&self) -> usize {
+        self.max_clicque
+    }
+    fn events_len(&self) -> usize {
+        self.inner.events.len()
+    }
+    fn events(&self) -> Vec<Event> {
+        self.inner.events.iter().copied().collect()
+    }
+    fn valid_new_segments(&self) -> Vec<(usize, usize)> {
+        self.valid_new_segments()
+    }
+    fn valid_new_segments_with_demand(&self, demand: usize) -> Vec<(usize, usize)> {
+        self.valid_new_segments_with_demand(demand)
+    }
+    fn uncollisions(&self, start: usize, end: usize) -> Vec<u8> {
+        self.uncollisions(start, end)
+    }
+    fn segment_uncollisions(&self, start_index: usize, end_index: usize) -> Vec<u8> {
+        self.uncollisions(start_index + 1, end_index)
+    }
+    fn valid_recolourings(&self) -> Vec<History> {
+        self.valid_recolourings()
+    }
+    fn removable_segments(&self) -> Vec<(usize, usize)> {
+        self.removable_segments()
+    }
+    fn reductions(&self) -> &'static [History] {
+        &[History::LimitFront, History::LimitBack]
+    }
+    fn normalize(&mut self) -> NormalizedState {
+        self.strategy_normalize_without_symmetry()
+    }
+    fn flipped(&mut self, state: &NormalizedState) -> NormalizedState {
+        let colors = self.max_colors();
+        state.flipped(&mut self.normalizer, colors)
+    }
+    fn key(&mut self) -> StateKey {
+        self.normalize_key()
+    }
+    fn raw_key(&self) -> StateKey {
+        StateKey::from_events(self.inner.events.iter())
+    }
+    fn flipped_key(&mut self, key: &StateKey) -> StateKey {
+        let colors = self.max_colors();
+        self.normalizer
+            .normalize_key(key.mirrored().events().iter(), colors)
+    }
+    fn normalize_move(&mut self, mov: StrategyMove) -> StrategyMove {
+        self.strategy_normalize_without_symmetry();
+        let colors = self.max_colors();
+        mov.with_list(|list| self.normalizer.normalize_colours(list, colors))
+    }
+    fn with_normalized(&self, state: &NormalizedState) -> Self {
+        ClicquedLinearAxis::with_inner(
+            LinearAxis::from_strategy_state(self.strategy_state(state)),
+            self.max_clicque,
+        )
+    }
+    fn strategy_state(&self, state: &NormalizedState) -> StrategyState {
+        StrategyState::from(state, self.max_colors())
+    }
+}
+
+/// A line of play found by `Game::beam_search`: each position with Spoiler's move from it,
+/// against the Algorithm's answers that held Spoiler to the fewest colours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeamLine {
+    pub steps: Vec<(StrategyState, StrategyMove)>,
+    /// The colours the line reaches, capped at `force_num_colours`.
+    pub colours: usize,
+}
+
+/// Positions by their value, the colours on the board, and the shorter board first.
+type BeamRank = (isize, usize, Reverse<usize>);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum StateStatus {
     True(Option<StrategyMove>),
@@ -28,13 +189,78 @@ impl StateStatus {
         }
     }
 }
+
+impl Record for StateStatus {
+    const SIZE: usize = 9;
+    fn write(&self, bytes: &mut [u8]) {
+        match *self {
+            StateStatus::Active => bytes[0] = 0,
+            StateStatus::False(max) => {
+                bytes[0] = 1;
+                bytes[1..9].copy_from_slice(&(max as i64).to_le_bytes());
+            }
+            StateStatus::True(None) => bytes[0] = 2,
+            StateStatus::True(Some(mov)) => {
+                let (tag, start, end, extra) = match mov {
+                    StrategyMove::Insert { start, end } => (3, start, end, 0),
+                    StrategyMove::InsertWithList { start, end, list } => (4, start, end, list),
+                    StrategyMove::InsertWithDemand { start, end, demand } => {
+                        (5, start, end, demand as u32)
+                    }
+                    StrategyMove::Remove { start, end } => (6, start, end, 0),
+                    StrategyMove::LimitFront => (7, 0, 0, 0),
+                    StrategyMove::LimitBack => (8, 0, 0, 0),
+                };
+                bytes[0] = tag;
+                bytes[1] = start as u8;
+                bytes[2] = end as u8;
+                bytes[3..7].copy_from_slice(&extra.to_le_bytes());
+            }
+        }
+    }
+    fn read(bytes: &[u8]) -> Self {
+        let (start, end) = (bytes[1] as usize, bytes[2] as usize);
+        let extra = u32::from_le_bytes(bytes[3..7].try_into().unwrap());
+        let mov = match bytes[0] {
+            0 => return StateStatus::Active,
+            1 => {
+                return StateStatus::False(
+                    i64::from_le_bytes(bytes[1..9].try_into().unwrap()) as isize
+                )
+            }
+            2 => return StateStatus::True(None),
+            3 => StrategyMove::Insert { start, end },
+            4 => StrategyMove::InsertWithList {
+                start,
+                end,
+                list: extra,
+            },
+            5 => StrategyMove::InsertWithDemand {
+                start,
+                end,
+                demand: extra as usize,
+            },
+            6 => StrategyMove::Remove { start, end },
+            7 => StrategyMove::LimitFront,
+            _ => StrategyMove::LimitBack,
+        };
+        StateStatus::True(Some(mov))
+    }
+}
+
+impl Replaceable for StateStatus {
+    /// Losses can be searched again, wins are needed to walk the strategy.
+    fn replaceable(&self) -> bool {
+        matches!(self, StateStatus::False(_))
+    }
+}
 #[derive()]
-pub struct Game {
-    axis: ClicquedLinearAxis,
+pub struct Game<A: GameAxis = ClicquedLinearAxis> {
+    axis: A,
     history: Vec<History>,
     force_num_colours: usize,
     max_events: usize,
-    states: HashMap<NormalizedState, StateStatus, GlobalRandomState>,
+    states: Box<dyn StateStore<StateKey, StateStatus>>,
     #[allow(dead_code)]
     reductees: HashMap<
         NormalizedState,
@@ -42,6 +268,40 @@ pub struct Game {
         GlobalRandomState,
     >,
     pub strategy: Option<StrategyConsumer>,
+    /// How many segments the Algorithm may recolour after each insertion.
+    pub recourse: usize,
+    /// How many segments Spoiler has to commit to in advance.
+    /// The Algorithm colours a segment only once `lookahead` more are queued behind it.
+    pub lookahead: usize,
+    /// The size of the list Spoiler hands out with every segment, `0` means any colour is allowed.
+    pub list_size: usize,
+    /// The most colours Spoiler may ask for a single segment (online multicolouring).
+    pub max_demand: usize,
+    /// Spoiler shrinks the board by deleting segments instead of moving the barriers.
+    pub deletions: bool,
+    /// The order Spoiler presents the segments in.
+    pub presentation: Presentation,
+    /// Before expanding a position, look for a won one Spoiler can reach by moving the barriers several times,
+    /// where `check_reductions` only tries a single move. Sound by `dominance::dominates`.
+    pub barrier_lookahead: bool,
+    /// Skips renormalizing positions met recently, see `NormalizationCache`.
+    pub normalization_cache: Option<NormalizationCache>,
+    /// Every move applied to the axis, undoing ones included, to replay a search without searching.
+    pub trace: Option<Vec<History>>,
+    /// The order Spoiler's insertions are tried in.
+    pub move_ordering: MoveOrdering,
+    move_history: MoveHistory,
+    nodes: usize,
+    /// Where `solve` gives up.
+    pub limits: SearchLimits,
+    budget: Budget,
+    /// Reports on the search while it runs.
+    pub progress: Option<ProgressReporter>,
+    most_colours: usize,
+    /// The move from the root being searched and how many there are.
+    root_move: (usize, usize),
+    /// Positions the search stopped at for lack of depth, see `frontier`.
+    frontier: Option<Vec<StateKey>>,
 }
 
 impl Game {
@@ -58,33 +318,286 @@ impl Game {
             strategy,
         )
     }
+    /// Records every move of the strategy as a win, e.g. one found by another process.
+    pub fn seed_strategy(&mut self, strategy: &Strategy) {
+        assert_eq!(self.max_clicque(), strategy.max_clicque);
+        for (state, &mov) in &strategy.moves {
+            let key = ClicquedLinearAxis::from_strategy_state(state.clone(), self.max_clicque())
+                .normalize_key();
+            self.states.insert(key, StateStatus::True(Some(mov)));
+        }
+    }
+}
+
+impl<A: GameAxis> Game<A> {
     pub fn with_axis(
-        axis: ClicquedLinearAxis,
+        axis: A,
         max_events: usize,
         force_num_colours: usize,
         strategy: Option<StrategyConsumer>,
     ) -> Self {
+        assert!(
+            max_events <= MAX_EVENTS,
+            "At most {MAX_EVENTS} events are supported, not {max_events}."
+        );
         Self {
             axis,
             history: vec![],
             force_num_colours,
             max_events,
-            states: HashMap::default(),
+            states: Box::new(Table::<StateKey, StateStatus>::default()),
             reductees: HashMap::default(),
             strategy,
+            recourse: 0,
+            lookahead: 0,
+            list_size: 0,
+            max_demand: 1,
+            deletions: false,
+            presentation: Presentation::Any,
+            barrier_lookahead: false,
+            normalization_cache: None,
+            trace: None,
+            move_ordering: MoveOrdering::Index,
+            move_history: MoveHistory::default(),
+            nodes: 0,
+            limits: SearchLimits::default(),
+            budget: Budget::default(),
+            progress: None,
+            most_colours: 0,
+            root_move: (0, 0),
+            frontier: None,
         }
     }
+    /// The current position, which is where a search starts and where it returns to.
+    pub fn position(&mut self) -> NormalizedState {
+        self.axis.normalize()
+    }
     pub fn register_winning_state(&mut self, state: NormalizedState) {
-        self.states.insert(state, StateStatus::True(None));
+        self.states
+            .insert(StateKey::from(&state), StateStatus::True(None));
+    }
+    pub fn max_events(&self) -> usize {
+        self.max_events
+    }
+    pub fn force_num_colours(&self) -> usize {
+        self.force_num_colours
+    }
+    pub fn max_clicque(&self) -> usize {
+        self.axis.max_clicque()
+    }
+    /// Searches from the given position instead of the current one, keeping everything learned so far.
+    pub fn solve_from(&mut self, state: &NormalizedState) -> usize {
+        let mut axis = self.axis.with_normalized(state);
+        std::mem::swap(&mut axis, &mut self.axis);
+        let result = self.forced_colours(-1);
+        std::mem::swap(&mut axis, &mut self.axis);
+        result
+    }
+    /// Records the verdict of a position solved elsewhere, as if the search had found it.
+    pub fn seed(&mut self, state: &NormalizedState, verdict: Verdict) {
+        let normalized = self.axis.with_normalized(state).key();
+        let status = match verdict {
+            Verdict::Win { mov, .. } => StateStatus::True(mov),
+            Verdict::Loss { colours } => StateStatus::False(colours as isize),
+        };
+        self.states.insert(normalized, status);
+    }
+    /// Every settled position with its verdict, wins with the number of Spoiler moves the strategy takes.
+    pub fn verdicts(&mut self) -> Vec<(NormalizedState, Verdict)> {
+        let mut distances = HashMap::default();
+        let mut settled = vec![];
+        self.states.for_each(&mut |state, &status| {
+            if status != StateStatus::Active {
+                settled.push((state.state(), status));
+            }
+        });
+        settled
+            .into_iter()
+            .map(|(state, status)| {
+                let verdict = match status {
+                    StateStatus::True(mov) => {
+                        let mut axis = self.axis.with_normalized(&state);
+                        std::mem::swap(&mut axis, &mut self.axis);
+                        let distance = self.distance(&mut distances);
+                        std::mem::swap(&mut axis, &mut self.axis);
+                        Verdict::Win {
+                            distance: distance.min(u8::MAX as usize) as u8,
+                            mov,
+                        }
+                    }
+                    StateStatus::False(max) => Verdict::Loss {
+                        colours: max.max(0) as u8,
+                    },
+                    StateStatus::Active => unreachable!(),
+                };
+                (state, verdict)
+            })
+            .collect()
     }
     pub fn simulate(&mut self, depth: isize) -> bool {
-        let result = self.simulate_inner(depth);
-        // dbg!(result);
-        let result = result >= self.force_num_colours as isize;
-        if result && self.strategy.is_some() {
+        self.solve(depth) == SearchOutcome::Win
+    }
+    /// Like `simulate`, within `limits`. Calling it again resumes the search with a fresh budget, from the wins found so far.
+    pub fn solve(&mut self, depth: isize) -> SearchOutcome {
+        self.budget = Budget::default();
+        let won = self.forced_colours(depth) >= self.force_num_colours;
+        if won && self.strategy.is_some() {
             self.walk_strategy(&mut HashSet::default());
         }
-        result
+        let outcome = self.budget.outcome(won);
+        // A loss depends on the positions that were on the search path when it was found,
+        // so only the wins are kept for the next call to build on.
+        if outcome == SearchOutcome::Unknown {
+            self.states
+                .retain(&mut |_, status| matches!(status, StateStatus::True(_)));
+        }
+        outcome
+    }
+    /// The positions a search `plies` moves deep has to leave unsettled, each once up to the mirror.
+    /// Settling them all settles the current position, see `jobs::Split`.
+    /// The table is left with depth limited values.
+    pub fn frontier(&mut self, plies: usize) -> Vec<StrategyState> {
+        self.frontier = Some(vec![]);
+        self.forced_colours(plies as isize);
+        let mut seen = HashSet::<StateKey, GlobalRandomState>::default();
+        let mut frontier = vec![];
+        for key in self.frontier.take().unwrap() {
+            if seen.contains(&key)
+                || (self.presentation.is_symmetric() && seen.contains(&self.axis.flipped_key(&key)))
+            {
+                continue;
+            }
+            seen.insert(key);
+            frontier.push(self.axis.strategy_state(&key.state()));
+        }
+        frontier
+    }
+    /// Looks for a line reaching many colours without proving anything: every round keeps the `width`
+    /// best moves over all positions in the beam, each valued by the worst of the Algorithm's answers.
+    /// Positions are valued by a search `depth` moves deep, the `StateStatus::False` it leaves behind,
+    /// and ties go to more colours on the board, then to shorter boards.
+    /// The table is left with depth limited values, so proofs need a fresh `Game`.
+    pub fn beam_search(&mut self, width: usize, depth: isize, rounds: usize) -> BeamLine {
+        let root = self.axis.normalize();
+        let mut best = BeamLine {
+            steps: vec![],
+            colours: self.axis.colours_used(),
+        };
+        let mut beam = vec![(root.clone(), vec![])];
+        for _ in 0..rounds {
+            let mut candidates = vec![];
+            for (state, line) in &beam {
+                self.axis = self.axis.with_normalized(state);
+                let moves = if self.axis.events_len() >= self.max_events {
+                    self.shrinking_moves()
+                } else {
+                    self.ordered_moves()
+                };
+                for mov in moves {
+                    let Some((rank, child)) = self.worst_answer(mov, depth) else {
+                        continue;
+                    };
+                    let mut line = line.clone();
+                    line.push((state.clone(), mov));
+                    candidates.push((rank, child, line));
+                }
+            }
+            candidates.sort_by_key(|&(rank, ..)| Reverse(rank));
+            let mut seen = HashSet::<_, GlobalRandomState>::default();
+            candidates
+                .retain(|(_, child, _)| child.as_ref().is_none_or(|c| seen.insert(c.clone())));
+            candidates.truncate(width);
+            let Some(((value, ..), _, line)) = candidates.first() else {
+                break;
+            };
+            let colours = (*value).clamp(0, self.force_num_colours as isize) as usize;
+            if colours > best.colours || best.steps.is_empty() {
+                best = BeamLine {
+                    steps: line
+                        .iter()
+                        .map(|(state, mov)| (self.axis.strategy_state(state), *mov))
+                        .collect(),
+                    colours,
+                };
+            }
+            if colours >= self.force_num_colours {
+                break;
+            }
+            beam = candidates
+                .into_iter()
+                .filter_map(|(_, child, line)| Some((child?, line)))
+                .collect();
+        }
+        self.axis = self.axis.with_normalized(&root);
+        best
+    }
+    /// The Algorithm's answer to `mov` that holds Spoiler to the fewest colours, ranked as in `beam_search`,
+    /// with the position it leaves. There is no position when Spoiler wins outright.
+    fn worst_answer(
+        &mut self,
+        mov: StrategyMove,
+        depth: isize,
+    ) -> Option<(BeamRank, Option<NormalizedState>)> {
+        if let Some(reduction) = mov.history() {
+            let reverse = self.apply_reduction(reduction)?;
+            let value = self.simulate_inner(depth);
+            let ranked = self.rank_position(value);
+            self.apply_history(reverse);
+            return Some(ranked);
+        }
+        let mut worst: Option<(BeamRank, Option<NormalizedState>)> = None;
+        for answer in self.responses(mov) {
+            let reverse = self.apply_all(&answer);
+            let value = self.simulate_recolourings(depth, self.recourse);
+            let ranked = self.rank_position(value);
+            self.apply_all(&reverse);
+            if worst.as_ref().is_none_or(|(rank, _)| ranked.0 < *rank) {
+                worst = Some(ranked);
+            }
+        }
+        // No colour fits, the Algorithm is beaten.
+        Some(worst.unwrap_or(((100, 0, Reverse(0)), None)))
+    }
+    fn rank_position(&mut self, value: isize) -> (BeamRank, Option<NormalizedState>) {
+        let state = (value < self.force_num_colours as isize).then(|| self.axis.normalize());
+        (
+            (
+                value,
+                self.axis.colours_used(),
+                Reverse(self.axis.events_len()),
+            ),
+            state,
+        )
+    }
+    /// The number of colours Spoiler can force from the current state, capped at `force_num_colours`.
+    /// Calling it again after a search only looks the answer up.
+    pub fn forced_colours(&mut self, depth: isize) -> usize {
+        assert!(
+            self.list_size == 0 || self.lookahead == 0,
+            "Lists cannot be handed out with segments queued in advance."
+        );
+        assert!(
+            self.max_events + 2 * (self.max_demand - 1) <= MAX_EVENTS,
+            "Segments demanding {} colours do not fit on boards of {} events.",
+            self.max_demand,
+            self.max_events
+        );
+        let mut result = self.simulate_inner(depth);
+        // Deletions let positions repeat, so a position met again while still being searched counts as lost
+        // and what was concluded from it may be too pessimistic. Searching again, keeping only the wins,
+        // settles it once no new wins turn up.
+        while self.deletions && result < self.force_num_colours as isize && !self.budget.exhausted()
+        {
+            let wins = self.number_of_wins();
+            self.states
+                .retain(&mut |_, status| matches!(status, StateStatus::True(_)));
+            result = self.simulate_inner(depth);
+            if self.number_of_wins() == wins {
+                break;
+            }
+        }
+        // dbg!(result);
+        (result.max(0) as usize).min(self.force_num_colours)
     }
     fn simulate_inner(&mut self, depth: isize) -> isize {
         let normalized = self.normalize();
@@ -95,44 +608,362 @@ impl Game {
             self.report_success(None);
             return 100;
         }
-        if self.check_reductions() {
+        if !self.deletions && self.check_reductions() {
             return 100;
         }
+        if self.barrier_lookahead && !self.deletions {
+            if let Some(won) = self.check_barrier_lookahead(false) {
+                debug_assert!(dominates(&normalized.state(), &won.state()));
+                return 100;
+            }
+        }
         if depth == 0 {
+            self.reach_frontier(normalized);
             return self.axis.colours_used() as isize;
         }
 
-        self.states.insert(normalized.clone(), StateStatus::Active);
+        if !self.enter(normalized) {
+            return self.axis.colours_used() as isize;
+        }
+        let nodes = self.nodes;
 
-        if self.axis.inner.events.len() >= self.max_events {
-            return self.force_reductions(depth);
+        if self.axis.events_len() >= self.max_events {
+            let result = self.force_reductions(depth);
+            self.leave(normalized, result, None);
+            return result;
         }
         let mut max = self.axis.colours_used() as isize;
-        for (s, e) in self.axis.valid_new_segments() {
+        for mov in self.ordered_moves() {
+            self.count_root_move();
             let mut min = 100;
-            for c in self.uncollisions(s, e) {
-                let mov = History::SegmentInsert {
-                    start_index: s,
-                    end_index: e,
-                    color: c,
-                };
-                let reverse = self.apply_history(mov).unwrap();
-                let result = self.simulate_inner(depth - 1);
-                self.apply_history(reverse);
+            for response in self.responses(mov) {
+                let reverse = self.apply_all(&response);
+                let result = self.simulate_recolourings(depth - 1, self.recourse);
+                self.apply_all(&reverse);
                 min = min.min(result);
+                if self.budget.exhausted() {
+                    break;
+                }
             }
             max = max.max(min);
             if max >= self.force_num_colours as isize {
-                self.report_success(Some(StrategyMove::Insert { start: s, end: e }));
+                if self.move_ordering == MoveOrdering::Killer {
+                    self.move_history.record(self.axis.events_len(), mov);
+                }
+                self.report_success(Some(mov));
+                return 100;
+            }
+            if self.budget.exhausted() {
+                break;
+            }
+        }
+        if self.deletions {
+            max = max.max(self.force_reductions(depth));
+            if max >= self.force_num_colours as isize {
                 return 100;
             }
         }
-        self.states.insert(normalized, StateStatus::False(max));
+        self.leave(normalized, max, Some(nodes));
         max
     }
+    // The bookkeeping of `simulate_inner` is kept out of it, so its frame stays small enough for deep searches.
+    #[inline(never)]
+    fn reach_frontier(&mut self, normalized: StateKey) {
+        if let Some(frontier) = &mut self.frontier {
+            frontier.push(normalized);
+        }
+    }
+    /// Marks the position as being searched and counts it, returns whether the budget lets the search go on.
+    #[inline(never)]
+    fn enter(&mut self, normalized: StateKey) -> bool {
+        self.states.insert(normalized, StateStatus::Active);
+        self.nodes += 1;
+        if self.budget.spend(&self.limits, self.table_bytes()) {
+            self.states.remove(&normalized);
+            return false;
+        }
+        if self.progress.is_some() {
+            self.update_progress();
+        }
+        true
+    }
+    /// Settles the position at `result`, or drops it if the search stopped before it was won.
+    /// Without `nodes`, where the count stood on entering, the table is left to the search that found `result`.
+    #[inline(never)]
+    fn leave(&mut self, normalized: StateKey, result: isize, nodes: Option<usize>) {
+        // Only part of the moves were tried, so nothing is known about the position.
+        if self.budget.exhausted() && result < self.force_num_colours as isize {
+            self.states.remove(&normalized);
+        } else if let Some(nodes) = nodes {
+            self.states.insert_with_work(
+                normalized,
+                StateStatus::False(result),
+                self.nodes - nodes,
+            );
+        }
+    }
+    #[inline(never)]
+    fn count_root_move(&mut self) {
+        if self.history.is_empty() {
+            self.root_move.0 += 1;
+        }
+    }
+    fn update_progress(&mut self) {
+        self.most_colours = self.most_colours.max(self.axis.colours_used());
+        if !self.progress.as_ref().is_some_and(|p| p.due(self.nodes)) {
+            return;
+        }
+        let progress = Progress {
+            seconds: 0.0,
+            states: self.states.len(),
+            nodes: self.nodes,
+            nodes_per_second: 0.0,
+            depth: self.history.len(),
+            most_colours: self.most_colours,
+            table_bytes: self.table_bytes(),
+            root_move: self.root_move.0,
+            root_moves: self.root_move.1,
+        };
+        self.progress.as_mut().unwrap().report(progress);
+    }
+    /// `spoiler_moves` in the order of `move_ordering`, ties kept from left to right.
+    /// At the root, the progress reported starts counting them.
+    fn ordered_moves(&mut self) -> Vec<StrategyMove> {
+        let mut moves = self.spoiler_moves();
+        if self.history.is_empty() {
+            self.root_move = (0, moves.len());
+        }
+        match self.move_ordering {
+            MoveOrdering::Index => {}
+            MoveOrdering::FewestResponses => {
+                moves.sort_by_cached_key(|&mov| self.responses(mov).len())
+            }
+            MoveOrdering::Killer => {
+                let events = self.axis.events_len();
+                moves.sort_by_cached_key(|&mov| {
+                    std::cmp::Reverse(self.move_history.score(events, mov))
+                })
+            }
+            MoveOrdering::PriorWin => moves.sort_by_cached_key(|&mov| self.prior_wins(mov)),
+        }
+        moves
+    }
+    /// Whether an answer to `mov` is already known to hold Spoiler off, and how many are not known to be won.
+    fn prior_wins(&mut self, mov: StrategyMove) -> (bool, usize) {
+        let force = self.force_num_colours as isize;
+        let mut lost = false;
+        let mut unknown = 0;
+        for response in self.responses(mov) {
+            let reverse = self.apply_all(&response);
+            let normalized = self.normalize();
+            match self.get_state(&normalized) {
+                Some(StateStatus::True(_)) => {}
+                Some(StateStatus::False(max)) if max < force => lost = true,
+                _ => unknown += 1,
+            }
+            self.apply_all(&reverse);
+        }
+        (lost, unknown)
+    }
+    /// The insertions Spoiler may try, with every list worth offering in the list colouring game.
+    fn spoiler_moves(&self) -> Vec<StrategyMove> {
+        if self.max_demand > 1 {
+            return (1..=self.max_demand)
+                .flat_map(|demand| {
+                    self.in_order(self.axis.valid_new_segments_with_demand(demand))
+                        .into_iter()
+                        .map(move |(start, end)| match demand {
+                            1 => StrategyMove::Insert { start, end },
+                            _ => StrategyMove::InsertWithDemand { start, end, demand },
+                        })
+                })
+                .collect();
+        }
+        let segments = self.in_order(self.axis.valid_new_segments());
+        if self.list_size == 0 {
+            return segments
+                .into_iter()
+                .map(|(start, end)| StrategyMove::Insert { start, end })
+                .collect();
+        }
+        let lists = self.lists();
+        segments
+            .into_iter()
+            .flat_map(|(start, end)| {
+                lists
+                    .iter()
+                    .map(move |&list| StrategyMove::InsertWithList { start, end, list })
+            })
+            .collect()
+    }
+    fn in_order(&self, segments: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        if self.presentation.is_symmetric() {
+            return segments;
+        }
+        let events = self.axis.events();
+        segments
+            .into_iter()
+            .filter(|&(start, end)| {
+                self.presentation
+                    .allows(start, end, events.iter().map(Event::is_start))
+            })
+            .collect()
+    }
+    /// Lists of `list_size` colours, up to renaming of the colours missing from the board.
+    fn lists(&self) -> Vec<u32> {
+        let max_colors = self.axis.max_colors();
+        let used = self
+            .axis
+            .events()
+            .iter()
+            .filter(|e| !e.is_pending())
+            .fold(0u32, |acc, e| acc | 1 << e.colour());
+        let fresh = (0..max_colors as u32)
+            .filter(|c| used & (1 << c) == 0)
+            .collect::<Vec<_>>();
+        let mut lists = vec![];
+        let mut subset = used;
+        loop {
+            let taken = subset.count_ones() as usize;
+            if taken <= self.list_size && taken + fresh.len() >= self.list_size {
+                let filler = fresh[..self.list_size - taken]
+                    .iter()
+                    .fold(0, |acc, c| acc | 1 << c);
+                lists.push(subset | filler);
+            }
+            if subset == 0 {
+                break;
+            }
+            subset = (subset - 1) & used;
+        }
+        lists
+    }
+    /// Everything the Algorithm may answer to Spoiler's insertion.
+    /// Each answer is a sequence of moves to be applied one after another.
+    fn responses(&mut self, mov: StrategyMove) -> Vec<Vec<History>> {
+        let (start, end, list) = match mov {
+            StrategyMove::Insert { start, end } => (start, end, u32::MAX),
+            StrategyMove::InsertWithList { start, end, list } => (start, end, list),
+            StrategyMove::InsertWithDemand { start, end, demand } => {
+                return self.demand_responses(start, end, demand)
+            }
+            limit => panic!("Not an insertion: {limit:?}"),
+        };
+        if self.lookahead == 0 {
+            return self
+                .uncollisions(start, end)
+                .into_iter()
+                .filter(|c| list & (1 << c) != 0)
+                .map(|c| {
+                    vec![History::SegmentInsert {
+                        start_index: start,
+                        end_index: end,
+                        color: c,
+                    }]
+                })
+                .collect();
+        }
+        let queued = pending_segments(&self.axis.events()).len();
+        let commit = History::SegmentInsert {
+            start_index: start,
+            end_index: end,
+            color: PENDING_COLOUR + queued as u8,
+        };
+        if queued < self.lookahead {
+            return vec![vec![commit]];
+        }
+        let reverse = self.axis.apply_history(commit).unwrap();
+        let pending = pending_segments(&self.axis.events());
+        let (head_start, head_end) = pending[0];
+        let colours = self.axis.segment_uncollisions(head_start, head_end);
+        self.axis.apply_history(reverse);
+        colours
+            .into_iter()
+            .map(|c| {
+                let mut response = vec![
+                    commit,
+                    History::SegmentRecolour {
+                        start_index: head_start,
+                        end_index: head_end,
+                        color: c,
+                    },
+                ];
+                // Everything behind the head moves one place up the queue.
+                for (i, &(start_index, end_index)) in pending.iter().enumerate().skip(1) {
+                    response.push(History::SegmentRecolour {
+                        start_index,
+                        end_index,
+                        color: PENDING_COLOUR + i as u8 - 1,
+                    });
+                }
+                response
+            })
+            .collect()
+    }
+    /// The Algorithm gives the segment `demand` distinct colours, one to each of its tied copies.
+    fn demand_responses(&self, start: usize, end: usize, demand: usize) -> Vec<Vec<History>> {
+        let used = self
+            .axis
+            .events()
+            .iter()
+            .fold(0u64, |acc, e| acc | 1 << e.colour());
+        let colours = self.uncollisions(start, end);
+        // Colours absent from the board are interchangeable, so only the first few of them are used.
+        let fresh = colours
+            .iter()
+            .copied()
+            .filter(|&c| used & (1 << c) == 0)
+            .collect::<Vec<_>>();
+        combinations(&colours, demand)
+            .into_iter()
+            .filter(|chosen| {
+                let unused = chosen.iter().filter(|c| fresh.contains(c));
+                unused.clone().eq(&fresh[..unused.count()])
+            })
+            .map(|chosen| {
+                let mut response = chosen
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &color)| History::SegmentInsert {
+                        start_index: start + i,
+                        end_index: end + 2 * i,
+                        color,
+                    })
+                    .collect::<Vec<_>>();
+                response.extend((0..demand - 1).map(|i| History::SegmentTie {
+                    start_index: start + i,
+                    end_index: end + demand + i,
+                }));
+                response
+            })
+            .collect()
+    }
+    /// Applies the moves in order and returns what undoes them.
+    fn apply_all(&mut self, moves: &[History]) -> Vec<History> {
+        let mut reverse = moves
+            .iter()
+            .map(|&mv| self.apply_history(mv).unwrap())
+            .collect::<Vec<_>>();
+        reverse.reverse();
+        reverse
+    }
+    /// The Algorithm answers an insertion by recolouring up to `remaining` segments,
+    /// one at a time, and picks the outcome that is worst for Spoiler.
+    fn simulate_recolourings(&mut self, depth: isize, remaining: usize) -> isize {
+        let mut min = self.simulate_inner(depth);
+        if remaining == 0 {
+            return min;
+        }
+        for recolour in self.axis.valid_recolourings() {
+            let reverse = self.apply_history(recolour).unwrap();
+            min = min.min(self.simulate_recolourings(depth, remaining - 1));
+            self.apply_history(reverse);
+        }
+        min
+    }
     fn check_reductions(&mut self) -> bool {
-        for reduction in [History::LimitFront, History::LimitBack] {
-            let reverse = if let Some(r) = self.apply_history(reduction) {
+        for reduction in self.reductions() {
+            let reverse = if let Some(r) = self.apply_reduction(reduction) {
                 r
             } else {
                 return false;
@@ -146,37 +977,150 @@ impl Game {
         }
         false
     }
+    /// Looks for a won position left after moving the barriers any number of times, front ones first.
+    /// Every position on the way is marked as won, so the strategy can be walked through it.
+    fn check_barrier_lookahead(&mut self, back_only: bool) -> Option<StateKey> {
+        for reduction in self.reductions() {
+            if back_only && reduction == History::LimitFront {
+                continue;
+            }
+            let Some(reverse) = self.apply_reduction(reduction) else {
+                continue;
+            };
+            let normalized = self.normalize();
+            let won = match self.get_state(&normalized) {
+                Some(StateStatus::True(_)) => Some(normalized),
+                _ => self.check_barrier_lookahead(reduction == History::LimitBack),
+            };
+            self.apply_history(reverse);
+            if won.is_some() {
+                self.report_success(Some(reduction.strategy_move().unwrap()));
+                return won;
+            }
+        }
+        None
+    }
+    /// The moves Spoiler shrinks the board with: barriers, or deletions in the dynamic game.
+    fn shrinking_moves(&self) -> Vec<StrategyMove> {
+        if self.deletions {
+            return self
+                .axis
+                .removable_segments()
+                .into_iter()
+                .map(|(start, end)| StrategyMove::Remove { start, end })
+                .collect();
+        }
+        self.reductions()
+            .into_iter()
+            .map(|reduction| reduction.strategy_move().unwrap())
+            .collect()
+    }
+    /// The barriers Spoiler may move. In an ordered presentation the back barrier would
+    /// forget the segment every later one has to come after, so only the front one moves.
+    fn reductions(&self) -> Vec<History> {
+        self.axis
+            .reductions()
+            .iter()
+            .copied()
+            .filter(|&r| self.presentation.is_symmetric() || r == History::LimitFront)
+            .collect()
+    }
     fn force_reductions(&mut self, depth: isize) -> isize {
         let mut max = self.axis.colours_used() as isize;
-        for reduction in [History::LimitFront, History::LimitBack] {
-            let reverse = self.apply_history(reduction).unwrap();
+        for mov in self.shrinking_moves() {
+            let Some(reverse) = self.apply_reduction(mov.history().unwrap()) else {
+                continue;
+            };
             let result = self.simulate_inner(depth - 1);
             self.apply_history(reverse);
             if result >= self.force_num_colours as isize {
-                self.report_success(Some(reduction.strategy_move().unwrap()));
+                self.report_success(Some(mov));
                 return result;
             }
             max = result.max(max);
         }
         max
     }
+    /// Like `apply_history`, but refuses to cut off segments still waiting in the lookahead queue.
+    fn apply_reduction(&mut self, reduction: History) -> Option<History> {
+        if self.lookahead == 0 {
+            return self.apply_history(reduction);
+        }
+        let pending = |game: &Self| game.axis.events().iter().filter(|e| e.is_pending()).count();
+        let before = pending(self);
+        let reverse = self.apply_history(reduction)?;
+        if pending(self) != before {
+            self.apply_history(reverse);
+            return None;
+        }
+        Some(reverse)
+    }
     fn report_success(&mut self, mv: Option<StrategyMove>) {
         let normalized = self.normalize();
         if let Some(StateStatus::True(_)) = self.get_state(&normalized) {
             return;
         }
+        let mv = mv.map(|mv| self.axis.normalize_move(mv));
         self.states.insert(normalized, StateStatus::True(mv));
     }
-    fn normalize(&mut self) -> NormalizedState {
-        self.axis.strategy_normalize_without_symmetry()
+    fn normalize(&mut self) -> StateKey {
+        match &mut self.normalization_cache {
+            Some(cache) => cache.normalize(&self.axis.raw_key(), || self.axis.key()),
+            None => self.axis.key(),
+        }
     }
     pub fn number_of_states(&self) -> usize {
         self.states.len()
     }
+    /// An estimate of the memory taken by the table of positions.
+    pub fn table_bytes(&self) -> usize {
+        self.states.bytes()
+    }
+    /// Keeps the losses in the table within about `bytes`, see `Table::bounded`.
+    /// Wins are kept regardless, so the strategy can be walked.
+    pub fn bound_table(&mut self, bytes: usize) {
+        self.replace_store(Box::new(Table::<StateKey, StateStatus>::bounded(bytes)));
+    }
+    /// Keeps the table on disk in `dir`, with about `memory_bytes` of it in memory, see `DiskStore`.
+    pub fn store_on_disk(&mut self, dir: impl AsRef<Path>, memory_bytes: usize) -> io::Result<()> {
+        self.replace_store(Box::new(DiskStore::<StateKey, StateStatus>::new(
+            dir,
+            memory_bytes,
+        )?));
+        Ok(())
+    }
+    fn replace_store(&mut self, mut states: Box<dyn StateStore<StateKey, StateStatus>>) {
+        self.states
+            .for_each(&mut |&state, &status| states.insert(state, status));
+        self.states = states;
+    }
+    /// How many losses the table dropped to make room.
+    pub fn evicted_states(&self) -> usize {
+        self.states.evicted()
+    }
+    /// How many positions were expanded, counting those searched again.
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+    fn number_of_wins(&self) -> usize {
+        let mut wins = 0;
+        self.states.for_each(&mut |_, status| {
+            wins += matches!(status, StateStatus::True(_)) as usize;
+        });
+        wins
+    }
     fn apply_history(&mut self, mv: History) -> Option<History> {
         let result = self.axis.apply_history(mv);
+        if let (Some(trace), Some(_)) = (&mut self.trace, result) {
+            trace.push(mv);
+        }
         match mv {
-            History::LimitBack | History::LimitFront | History::SegmentInsert { .. } => {
+            History::LimitBack
+            | History::LimitFront
+            | History::SegmentInsert { .. }
+            | History::SegmentRecolour { .. }
+            | History::SegmentTie { .. }
+            | History::SegmentDelete { .. } => {
                 if result.is_some() {
                     self.history.push(mv);
                 }
@@ -188,19 +1132,36 @@ impl Game {
 
         result
     }
-    fn get_state(&mut self, normalized: &NormalizedState) -> Option<&StateStatus> {
-        let colors = self.axis.max_colors();
-        self.states.get(&normalized).or(self
-            .states
-            .get(&normalized.flipped(&mut self.axis.normalizer, colors)))
+    fn get_state(&mut self, normalized: &StateKey) -> Option<StateStatus> {
+        if !self.presentation.is_symmetric() || self.states.contains_key(normalized) {
+            return self.states.get(normalized);
+        }
+        let flipped = self.axis.flipped_key(normalized);
+        self.states.get(&flipped)
+    }
+    /// The key of the current position, which has to be won. One missing from the table is searched again.
+    fn won_normalised(&mut self) -> StateKey {
+        match self.get_actual_normalised() {
+            Some(normalized)
+                if matches!(self.states.get(&normalized), Some(StateStatus::True(_))) =>
+            {
+                normalized
+            }
+            _ => {
+                assert!(self.simulate_inner(-1) >= self.force_num_colours as isize);
+                self.get_actual_normalised().unwrap()
+            }
+        }
     }
-    fn get_actual_normalised(&mut self) -> Option<NormalizedState> {
+    fn get_actual_normalised(&mut self) -> Option<StateKey> {
         let normalized = self.normalize();
         if self.states.contains_key(&normalized) {
-            return Some(normalized.clone());
+            return Some(normalized);
+        }
+        if !self.presentation.is_symmetric() {
+            return None;
         }
-        let colors = self.axis.max_colors();
-        let flipped = normalized.flipped(&mut self.axis.normalizer, colors);
+        let flipped = self.axis.flipped_key(&normalized);
         if self.states.contains_key(&flipped) {
             return Some(flipped);
         }
@@ -209,45 +1170,91 @@ impl Game {
     fn uncollisions(&self, start: usize, end: usize) -> Vec<u8> {
         self.axis.uncollisions(start, end)
     }
-    fn walk_strategy(&mut self, walked: &mut HashSet<NormalizedState, GlobalRandomState>) {
-        let normalized = self.get_actual_normalised().unwrap();
-        let colors = self.axis.max_colors();
+    fn walk_recolourings(
+        &mut self,
+        walked: &mut HashSet<StateKey, GlobalRandomState>,
+        remaining: usize,
+    ) {
+        self.walk_strategy(walked);
+        if remaining == 0 {
+            return;
+        }
+        for recolour in self.axis.valid_recolourings() {
+            let reverse = self.apply_history(recolour).unwrap();
+            self.walk_recolourings(walked, remaining - 1);
+            self.apply_history(reverse);
+        }
+    }
+    fn distance(&mut self, distances: &mut HashMap<StateKey, usize, GlobalRandomState>) -> usize {
+        let normalized = self.won_normalised();
+        if let Some(&distance) = distances.get(&normalized) {
+            return distance;
+        }
+        let mut new_axis = self.axis.with_normalized(&normalized.state());
+        std::mem::swap(&mut new_axis, &mut self.axis);
+        let distance = match self.get_state(&normalized) {
+            Some(StateStatus::True(None)) => 0,
+            Some(StateStatus::True(Some(
+                insert @ (StrategyMove::Insert { .. }
+                | StrategyMove::InsertWithList { .. }
+                | StrategyMove::InsertWithDemand { .. }),
+            ))) => {
+                let mut distance = 0;
+                for response in self.responses(insert) {
+                    let reverse = self.apply_all(&response);
+                    distance = distance.max(self.distance(distances));
+                    self.apply_all(&reverse);
+                }
+                distance + 1
+            }
+            Some(StateStatus::True(Some(limit))) => {
+                let reverse = self.apply_reduction(limit.history().unwrap()).unwrap();
+                let distance = self.distance(distances);
+                self.apply_history(reverse);
+                distance + 1
+            }
+            e => panic!("Should be true: {e:?}"),
+        };
+        std::mem::swap(&mut new_axis, &mut self.axis);
+        distances.insert(normalized, distance);
+        distance
+    }
+    fn walk_strategy(&mut self, walked: &mut HashSet<StateKey, GlobalRandomState>) {
+        let normalized = self.won_normalised();
         if walked.contains(&normalized)
-            || walked.contains(&normalized.flipped(&mut self.axis.normalizer, colors))
+            || (self.presentation.is_symmetric()
+                && walked.contains(&self.axis.flipped_key(&normalized)))
         {
             return;
         } else {
-            walked.insert(normalized.clone());
+            walked.insert(normalized);
         }
-        let mut new_axis = ClicquedLinearAxis::with_inner(
-            LinearAxis::from_strategy_state(self.strategy_state()),
-            self.axis.max_clicque,
-        );
+        let mut new_axis = self.axis.with_normalized(&normalized.state());
         std::mem::swap(&mut new_axis, &mut self.axis);
-        match self.get_state(&normalized).copied() {
+        match self.get_state(&normalized) {
             Some(StateStatus::True(None)) => {
                 assert!(self.axis.colours_used() >= self.force_num_colours)
             }
             Some(StateStatus::True(Some(mv))) => match mv {
-                limit @ (StrategyMove::LimitBack | StrategyMove::LimitFront) => {
-                    self.strategy.as_mut().unwrap().consume(&normalized, limit);
-                    let reverse = self.apply_history(limit.history().unwrap()).unwrap();
+                limit @ (StrategyMove::LimitBack
+                | StrategyMove::LimitFront
+                | StrategyMove::Remove { .. }) => {
+                    let state = self.axis.strategy_state(&normalized.state());
+                    self.strategy.as_mut().unwrap().consume_state(state, limit);
+                    let reverse = self.apply_reduction(limit.history().unwrap()).unwrap();
                     self.walk_strategy(walked);
                     self.apply_history(reverse);
                 }
-                insert @ StrategyMove::Insert { start, end } => {
-                    self.strategy.as_mut().unwrap().consume(&normalized, insert);
-
-                    for c in self.uncollisions(start, end) {
-                        let reverse = self
-                            .apply_history(History::SegmentInsert {
-                                start_index: start,
-                                end_index: end,
-                                color: c,
-                            })
-                            .unwrap();
-                        self.walk_strategy(walked);
-                        self.apply_history(reverse);
+                insert @ (StrategyMove::Insert { .. }
+                | StrategyMove::InsertWithList { .. }
+                | StrategyMove::InsertWithDemand { .. }) => {
+                    let state = self.axis.strategy_state(&normalized.state());
+                    self.strategy.as_mut().unwrap().consume_state(state, insert);
+
+                    for response in self.responses(insert) {
+                        let reverse = self.apply_all(&response);
+                        self.walk_recolourings(walked, self.recourse);
+                        self.apply_all(&reverse);
                     }
                 }
             },
@@ -255,10 +1262,315 @@ impl Game {
         }
         std::mem::swap(&mut new_axis, &mut self.axis);
     }
-    fn strategy_state(&mut self) -> StrategyState {
-        StrategyState::from(
-            &self.get_actual_normalised().unwrap(),
-            self.axis.max_colors(),
-        )
+}
+
+/// All ways of picking `size` of the `items`, each kept in the original order.
+fn combinations(items: &[u8], size: usize) -> Vec<Vec<u8>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    let mut result = vec![];
+    for (i, &item) in items.iter().enumerate() {
+        for mut rest in combinations(&items[i + 1..], size - 1) {
+            rest.insert(0, item);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// Runs a test on a large stack, as `main` runs the search: deep searches overflow the default one in debug builds.
+#[cfg(test)]
+pub(crate) fn with_search_stack(test: impl FnOnce() + Send + 'static) {
+    let child = std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(test)
+        .unwrap();
+    if let Err(panic) = child.join() {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+#[test]
+fn test_recourse_weakens_spoiler() {
+    let mut game = Game::new(6, 2, 3, None);
+    assert!(game.simulate(-1));
+    let mut game = Game::new(6, 2, 3, None);
+    game.recourse = 2;
+    assert!(!game.simulate(-1));
+    let strategy = StrategyConsumer::new(3, 2, 3, Box::new(std::io::sink()));
+    let mut game = Game::new(8, 2, 3, Some(strategy));
+    game.recourse = 2;
+    assert!(game.simulate(-1));
+}
+
+#[test]
+fn test_lookahead() {
+    let mut game = Game::new(6, 2, 3, None);
+    game.lookahead = 1;
+    assert!(!game.simulate(-1));
+    let strategy = StrategyConsumer::new(3, 2, 3, Box::new(std::io::sink()));
+    let mut game = Game::new(8, 2, 3, Some(strategy));
+    game.lookahead = 1;
+    assert!(game.simulate(-1));
+}
+
+#[test]
+fn test_list_colouring() {
+    let mut game = Game::new(8, 2, 3, None);
+    game.list_size = 2;
+    assert_eq!(game.lists(), vec![0b011]);
+    game.apply_history(History::SegmentInsert {
+        start_index: 0,
+        end_index: 0,
+        color: 1,
+    });
+    assert_eq!(game.lists(), vec![0b011, 0b101]);
+    let strategy = StrategyConsumer::new(3, 2, 3, Box::new(std::io::sink()));
+    let mut game = Game::new(6, 2, 3, Some(strategy));
+    game.list_size = 2;
+    assert!(game.simulate(-1));
+}
+
+#[test]
+fn test_multicolouring() {
+    assert_eq!(
+        combinations(&[0, 1, 2], 2),
+        vec![vec![0, 1], vec![0, 2], vec![1, 2]]
+    );
+    let strategy = StrategyConsumer::new(3, 2, 3, Box::new(std::io::sink()));
+    let mut game = Game::new(6, 2, 3, Some(strategy));
+    game.max_demand = 2;
+    assert!(game.simulate(-1));
+}
+
+#[test]
+fn test_deletions() {
+    let mut game = Game::new(7, 3, 5, None);
+    game.deletions = true;
+    assert!(!game.simulate(-1));
+    let strategy = StrategyConsumer::new(5, 3, 5, Box::new(std::io::sink()));
+    let mut game = Game::new(10, 3, 5, Some(strategy));
+    game.deletions = true;
+    assert!(game.simulate(-1));
+}
+
+#[test]
+fn test_ordered_presentation() {
+    // First-Fit is optimal on segments presented from left to right, so Spoiler only forces the clicque.
+    for presentation in [Presentation::LeftEndpoint, Presentation::RightEndpoint] {
+        let mut game = Game::new(9, 3, 5, None);
+        game.presentation = presentation;
+        assert!(!game.simulate(-1));
+        assert_eq!(game.forced_colours(-1), 3);
+        let mut game = Game::new(
+            7,
+            2,
+            2,
+            Some(StrategyConsumer::new(4, 2, 2, Box::new(std::io::sink()))),
+        );
+        game.presentation = presentation;
+        assert!(game.simulate(-1));
+    }
+    let mut game = Game::new(5, 2, 3, None);
+    assert!(game.simulate(-1));
+}
+
+#[test]
+fn test_barrier_lookahead() {
+    with_search_stack(|| {
+        for max_events in [7, 9] {
+            let mut plain = Game::new(max_events, 3, 5, None);
+            let mut pruned = Game::new(max_events, 3, 5, None);
+            pruned.barrier_lookahead = true;
+            assert_eq!(plain.simulate(-1), pruned.simulate(-1));
+            assert!(pruned.number_of_states() < plain.number_of_states());
+            assert!(pruned.nodes() < plain.nodes());
+        }
+        let strategy = StrategyConsumer::new(10, 3, 5, Box::new(std::io::sink()));
+        let mut game = Game::new(9, 3, 5, Some(strategy));
+        game.barrier_lookahead = true;
+        assert!(game.simulate(-1));
+    });
+}
+
+#[test]
+fn test_normalization_cache() {
+    with_search_stack(|| {
+        let mut plain = Game::new(9, 3, 5, None);
+        let mut cached = Game::new(9, 3, 5, None);
+        cached.normalization_cache = Some(NormalizationCache::new(10, true));
+        assert_eq!(plain.simulate(-1), cached.simulate(-1));
+        assert_eq!(plain.number_of_states(), cached.number_of_states());
+        let cache = cached.normalization_cache.unwrap();
+        assert!(cache.hits > 0);
+        assert_eq!(cache.collisions, 0);
+    });
+}
+
+#[test]
+fn test_move_ordering() {
+    with_search_stack(|| {
+        use clap::ValueEnum;
+
+        let mut nodes = vec![];
+        for &ordering in MoveOrdering::value_variants() {
+            let mut game = Game::new(9, 3, 5, None);
+            game.move_ordering = ordering;
+            assert!(game.simulate(-1), "{ordering:?}");
+            nodes.push(game.nodes());
+        }
+        // Every heuristic finds the win expanding fewer positions than going from the left.
+        assert!(nodes[1..].iter().all(|&n| n < nodes[0]), "{nodes:?}");
+
+        // A position met again while still being searched counts as lost, so a loss may come from the order.
+        // Wins never do: going from the left misses this one.
+        let mut game = Game::new(7, 3, 5, None);
+        game.move_ordering = MoveOrdering::FewestResponses;
+        assert!(game.simulate(-1));
+    });
+}
+
+#[test]
+fn test_beam_search() {
+    let mut game = Game::new(9, 3, 5, None);
+    let line = game.beam_search(8, 1, 36);
+    assert_eq!(line.colours, 5);
+    assert_eq!(line.steps[0].0.to_string(), "[]");
+    // Moves from a position are numbered on that position.
+    assert!(matches!(
+        line.steps[0].1,
+        StrategyMove::Insert { start: 0, end: 0 }
+    ));
+
+    // Valuing positions by the colours on the board alone falls short.
+    let mut game = Game::new(9, 3, 5, None);
+    assert!(game.beam_search(4, 0, 36).colours < 5);
+}
+
+#[test]
+fn test_search_limits() {
+    with_search_stack(|| {
+        let mut game = Game::new(9, 3, 5, None);
+        game.limits.nodes = Some(100);
+        assert_eq!(game.solve(-1), SearchOutcome::Unknown);
+        // Only the wins are kept for the next call to build on.
+        assert!(game.number_of_wins() > 0);
+        assert_eq!(game.number_of_states(), game.number_of_wins());
+        game.limits.nodes = None;
+        assert_eq!(game.solve(-1), SearchOutcome::Win);
+
+        let mut game = Game::new(7, 3, 5, None);
+        game.limits.table_bytes = Some(1 << 20);
+        assert_eq!(game.solve(-1), SearchOutcome::Loss);
+        let mut game = Game::new(7, 3, 5, None);
+        game.limits.table_bytes = Some(1000);
+        assert_eq!(game.solve(-1), SearchOutcome::Unknown);
+    });
+}
+
+#[test]
+fn test_resume() {
+    with_search_stack(|| {
+        for (max_events, ordering) in [(9, MoveOrdering::Index), (7, MoveOrdering::FewestResponses)]
+        {
+            let mut fresh = Game::new(max_events, 3, 5, None);
+            fresh.move_ordering = ordering;
+            let verdict = fresh.solve(-1);
+            for nodes in [7, 10, 20] {
+                let mut game = Game::new(max_events, 3, 5, None);
+                game.move_ordering = ordering;
+                game.limits.nodes = Some(nodes);
+                for _ in 0..10 {
+                    let outcome = game.solve(-1);
+                    assert!(outcome == SearchOutcome::Unknown || outcome == verdict);
+                }
+                game.limits.nodes = None;
+                assert_eq!(game.solve(-1), verdict, "{max_events} {ordering:?} {nodes}");
+            }
+        }
+    });
+}
+
+#[test]
+fn test_progress() {
+    with_search_stack(|| {
+        use crate::progress::ProgressFormat;
+        use std::time::Duration;
+
+        let mut game = Game::new(10, 3, 5, None);
+        game.progress = Some(ProgressReporter::new(ProgressFormat::Json, Duration::ZERO));
+        assert!(game.simulate(-1));
+        assert_eq!(game.most_colours, 4);
+        assert!(game.root_move.0 >= 1 && game.root_move.0 <= game.root_move.1);
+    });
+}
+
+#[test]
+fn test_bounded_table() {
+    with_search_stack(|| {
+        let mut unbounded = Game::new(10, 3, 5, None);
+        assert!(unbounded.simulate(-1));
+
+        let strategy = StrategyConsumer::new(10, 3, 5, Box::new(std::io::sink()));
+        let mut game = Game::new(10, 3, 5, Some(strategy));
+        game.bound_table(16 << 10);
+        assert!(game.simulate(-1));
+        assert!(game.evicted_states() > 0);
+        assert!(game.number_of_states() < unbounded.number_of_states());
+
+        let mut game = Game::new(7, 3, 5, None);
+        game.bound_table(1 << 10);
+        assert!(!game.simulate(-1));
+
+        // Wins dropped from the table are searched again when the strategy is walked.
+        let strategy = StrategyConsumer::new(10, 3, 5, Box::new(std::io::sink()));
+        let mut game = Game::new(9, 3, 5, Some(strategy));
+        assert!(game.simulate(-1));
+        let root = game.normalize();
+        game.states
+            .retain(&mut |&state, status| state == root || !matches!(status, StateStatus::True(_)));
+        let wins = game.number_of_wins();
+        game.walk_strategy(&mut HashSet::default());
+        assert!(game.number_of_wins() > wins);
+    });
+}
+
+#[test]
+fn test_disk_store() {
+    with_search_stack(|| {
+        let mut in_memory = Game::new(9, 3, 5, None);
+        assert!(in_memory.simulate(-1));
+
+        let dir = std::env::temp_dir().join(format!("game-disk-store-{}", std::process::id()));
+        let strategy = StrategyConsumer::new(9, 3, 5, Box::new(std::io::sink()));
+        let mut game = Game::new(9, 3, 5, Some(strategy));
+        game.store_on_disk(&dir, 4 << 10).unwrap();
+        assert!(game.simulate(-1));
+        assert_eq!(game.number_of_wins(), in_memory.number_of_wins());
+        // Positions written again after a flush are counted once the runs are merged.
+        assert!(game.number_of_states() >= in_memory.number_of_states());
+        game.states.retain(&mut |_, _| true);
+        assert_eq!(game.number_of_states(), in_memory.number_of_states());
+        drop(game);
+        std::fs::remove_dir(&dir).unwrap();
+    });
+}
+
+#[test]
+fn test_max_events() {
+    assert_eq!(parse_max_events("38"), Ok(38));
+    assert!(parse_max_events("39").is_err());
+
+    // Boards kept outside the table may be larger.
+    let mut axis = ClicquedLinearAxis::new(3);
+    for i in 0..25 {
+        axis.apply_history(History::SegmentInsert {
+            start_index: 2 * i,
+            end_index: 2 * i,
+            color: i as u8 % 2,
+        })
+        .unwrap();
     }
+    assert_eq!(axis.strategy_normalize_without_symmetry().0.len(), 50);
 }
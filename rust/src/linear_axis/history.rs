@@ -23,4 +23,34 @@ pub enum History {
         start: Event,
         lost: usize,
     },
+    SegmentRecolour {
+        start_index: usize,
+        end_index: usize,
+        color: u8,
+    },
+    SegmentRestoreColour {
+        start_index: usize,
+        end_index: usize,
+        color: u8,
+    },
+    /// Spoiler deleting the segment with endpoints at `start_index` and `end_index`.
+    SegmentDelete {
+        start_index: usize,
+        end_index: usize,
+    },
+    /// Undoes `SegmentDelete`, putting the segment back with its endpoints at the same indices.
+    SegmentRestore {
+        start_index: usize,
+        end_index: usize,
+        color: u8,
+    },
+    /// Ties the segment to the one starting and ending right after it, making them copies of one segment.
+    SegmentTie {
+        start_index: usize,
+        end_index: usize,
+    },
+    SegmentUntie {
+        start_index: usize,
+        end_index: usize,
+    },
 }
@@ -0,0 +1,321 @@
+use std::io::{self, BufRead, Write};
+
+use crate::limits::SearchOutcome;
+
+use super::{
+    clicqued::ClicquedLinearAxis,
+    game::Game,
+    normalization::NormalizedState,
+    strategy::{Strategy, StrategyConsumer},
+    tablebase::Verdict,
+};
+
+/// A search from the empty board split into jobs that separate processes can solve:
+/// the positions the search reaches `plies` Spoiler moves in, and has yet to settle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Split {
+    pub max_clicque: usize,
+    pub force_num_colours: usize,
+    pub max_events: usize,
+    pub plies: usize,
+}
+
+/// A position of a `Split` to be solved on its own, in strategy-state notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub max_clicque: usize,
+    pub force_num_colours: usize,
+    pub max_events: usize,
+    pub state: String,
+}
+
+/// What solving a job, or the whole `Split`, concluded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobResult {
+    Win,
+    /// The most colours the search could force, not a proof, see `SearchOutcome::Loss`.
+    Loss {
+        colours: usize,
+    },
+    /// The search hit its limits, or some of the jobs are yet to be solved.
+    Unknown,
+}
+
+impl Split {
+    pub fn jobs(&self) -> Vec<Job> {
+        let mut game = Game::new(
+            self.max_events,
+            self.max_clicque,
+            self.force_num_colours,
+            None,
+        );
+        game.frontier(self.plies)
+            .into_iter()
+            .map(|state| Job {
+                max_clicque: self.max_clicque,
+                force_num_colours: self.force_num_colours,
+                max_events: self.max_events,
+                state: state.to_string(),
+            })
+            .collect()
+    }
+
+    /// Searches again from the empty board with the won jobs settled.
+    /// A job's loss is no proof, see `SearchOutcome::Loss`, so lost jobs are searched again in full,
+    /// while with jobs yet to be solved only the first `plies` moves are searched, for a win.
+    /// Won jobs need their strategies for the merged one to be written to `strategy`.
+    pub fn merge(
+        &self,
+        jobs: &[(Job, JobResult)],
+        strategies: &[Strategy],
+        strategy: Option<StrategyConsumer>,
+    ) -> JobResult {
+        let mut game = Game::new(
+            self.max_events,
+            self.max_clicque,
+            self.force_num_colours,
+            strategy,
+        );
+        for (job, _) in jobs.iter().filter(|(_, result)| *result == JobResult::Win) {
+            let won = Verdict::Win {
+                distance: 0,
+                mov: None,
+            };
+            game.seed(&job.position(), won);
+        }
+        for strategy in strategies {
+            game.seed_strategy(strategy);
+        }
+        if jobs.iter().any(|(_, result)| *result == JobResult::Unknown) {
+            return match game.solve(self.plies as isize) {
+                SearchOutcome::Win => JobResult::Win,
+                _ => JobResult::Unknown,
+            };
+        }
+        JobResult::solve(&mut game)
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "{} {} {} {}",
+            self.max_clicque, self.force_num_colours, self.max_events, self.plies
+        )
+    }
+
+    pub fn read(r: &mut impl BufRead) -> io::Result<Self> {
+        let [max_clicque, force_num_colours, max_events, plies] = read_numbers(r)?;
+        Ok(Self {
+            max_clicque,
+            force_num_colours,
+            max_events,
+            plies,
+        })
+    }
+}
+
+impl Job {
+    pub fn position(&self) -> NormalizedState {
+        self.axis().strategy_normalize_without_symmetry()
+    }
+
+    /// A game searching from the job's position.
+    pub fn game(&self, strategy: Option<StrategyConsumer>) -> Game {
+        Game::with_axis(
+            self.axis(),
+            self.max_events,
+            self.force_num_colours,
+            strategy,
+        )
+    }
+
+    fn axis(&self) -> ClicquedLinearAxis {
+        ClicquedLinearAxis::from_strategy_string(&self.state, self.max_clicque)
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "{} {} {}",
+            self.max_clicque, self.force_num_colours, self.max_events
+        )?;
+        writeln!(w, "{}", self.state)
+    }
+
+    pub fn read(r: &mut impl BufRead) -> io::Result<Self> {
+        let [max_clicque, force_num_colours, max_events] = read_numbers(r)?;
+        let mut state = String::new();
+        r.read_line(&mut state)?;
+        Ok(Self {
+            max_clicque,
+            force_num_colours,
+            max_events,
+            state: state.trim().to_string(),
+        })
+    }
+}
+
+impl JobResult {
+    /// Solves the job `game` was made for, see `Job::game`.
+    pub fn solve(game: &mut Game) -> Self {
+        match game.solve(-1) {
+            SearchOutcome::Win => JobResult::Win,
+            SearchOutcome::Loss => JobResult::Loss {
+                colours: game.forced_colours(-1),
+            },
+            SearchOutcome::Unknown => JobResult::Unknown,
+        }
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        match self {
+            JobResult::Win => writeln!(w, "win"),
+            JobResult::Loss { colours } => writeln!(w, "loss {colours}"),
+            JobResult::Unknown => writeln!(w, "unknown"),
+        }
+    }
+
+    pub fn read(r: &mut impl BufRead) -> io::Result<Self> {
+        let mut line = String::new();
+        r.read_line(&mut line)?;
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["win"] => Ok(JobResult::Win),
+            ["loss", colours] => Ok(JobResult::Loss {
+                colours: colours.parse().map_err(|_| invalid(&line))?,
+            }),
+            ["unknown"] => Ok(JobResult::Unknown),
+            _ => Err(invalid(&line)),
+        }
+    }
+}
+
+fn read_numbers<const N: usize>(r: &mut impl BufRead) -> io::Result<[usize; N]> {
+    let mut line = String::new();
+    r.read_line(&mut line)?;
+    line.split_whitespace()
+        .map(|n| n.parse().map_err(|_| invalid(&line)))
+        .collect::<io::Result<Vec<_>>>()?
+        .try_into()
+        .map_err(|_| invalid(&line))
+}
+
+fn invalid(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, line.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::BufReader};
+
+    use super::super::game::with_search_stack;
+    use super::*;
+
+    /// Solves every job of the split, with the strategies of the won ones.
+    fn solve_jobs(split: &Split) -> (Vec<(Job, JobResult)>, Vec<Strategy>) {
+        let path = std::env::temp_dir().join(format!(
+            "job-{}-{}.strategy",
+            split.max_events,
+            std::process::id()
+        ));
+        let mut strategies = vec![];
+        let results = split
+            .jobs()
+            .into_iter()
+            .map(|job| {
+                let mut written = vec![];
+                job.write(&mut written).unwrap();
+                assert_eq!(Job::read(&mut written.as_slice()).unwrap(), job);
+
+                let strategy = StrategyConsumer::new(
+                    2 * split.force_num_colours,
+                    split.max_clicque,
+                    split.force_num_colours,
+                    Box::new(File::create(&path).unwrap()),
+                );
+                let result = JobResult::solve(&mut job.game(Some(strategy)));
+                if result == JobResult::Win {
+                    let file = &mut BufReader::new(File::open(&path).unwrap());
+                    strategies.push(Strategy::read(file).unwrap());
+                }
+                (job, result)
+            })
+            .collect();
+        std::fs::remove_file(path).unwrap();
+        (results, strategies)
+    }
+
+    #[test]
+    fn test_split_and_merge() {
+        with_search_stack(|| {
+            let split = Split {
+                max_clicque: 3,
+                force_num_colours: 5,
+                max_events: 9,
+                plies: 2,
+            };
+            let mut written = vec![];
+            split.write(&mut written).unwrap();
+            assert_eq!(Split::read(&mut written.as_slice()).unwrap(), split);
+
+            let (results, strategies) = solve_jobs(&split);
+            assert!(results.len() > 1);
+            assert!(!strategies.is_empty());
+            let path = std::env::temp_dir().join(format!("merged-{}.strategy", std::process::id()));
+            let strategy = StrategyConsumer::new(10, 3, 5, Box::new(File::create(&path).unwrap()));
+            assert_eq!(
+                split.merge(&results, &strategies, Some(strategy)),
+                JobResult::Win
+            );
+            let merged = Strategy::read(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+            assert!(merged.moves.len() > strategies.iter().map(|s| s.moves.len()).max().unwrap());
+            std::fs::remove_file(path).unwrap();
+
+            // Lost jobs are searched again, so losses reported wrongly do not lose the merged search.
+            let lost = results
+                .iter()
+                .map(|(job, _)| (job.clone(), JobResult::Loss { colours: 0 }))
+                .collect::<Vec<_>>();
+            assert_eq!(split.merge(&lost, &[], None), JobResult::Win);
+
+            // Nothing is concluded before the won jobs are solved.
+            let unsolved = results
+                .into_iter()
+                .map(|(job, result)| match result {
+                    JobResult::Win => (job, JobResult::Unknown),
+                    result => (job, result),
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(split.merge(&unsolved, &[], None), JobResult::Unknown);
+        });
+    }
+
+    #[test]
+    fn test_merge_loss() {
+        let split = Split {
+            max_clicque: 3,
+            force_num_colours: 5,
+            max_events: 7,
+            plies: 1,
+        };
+        let (results, _) = solve_jobs(&split);
+        assert!(results
+            .iter()
+            .all(|(_, result)| matches!(result, JobResult::Loss { .. })));
+        let mut game = Game::new(7, 3, 5, None);
+        assert!(!game.simulate(-1));
+        assert_eq!(
+            split.merge(&results, &[], None),
+            JobResult::Loss {
+                colours: game.forced_colours(-1)
+            }
+        );
+
+        let mut written = vec![];
+        results[0].1.write(&mut written).unwrap();
+        assert_eq!(
+            JobResult::read(&mut written.as_slice()).unwrap(),
+            results[0].1
+        );
+        assert!(JobResult::read(&mut "lost\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,365 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{clicqued::ClicquedLinearAxis, strategy::StrategyMove, History};
+
+/// How the Algorithm colours segments once the search leaves the tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Rollout {
+    /// Any colour that fits, uniformly.
+    #[default]
+    Random,
+    /// The lowest colour that fits.
+    FirstFit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edge {
+    Spoiler(StrategyMove),
+    Colour(u8),
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    visits: usize,
+    /// Sum of the rewards, from Spoiler's side.
+    total: f64,
+    most_colours: usize,
+    /// The segment waiting for the Algorithm's colour, `None` when it is Spoiler's turn.
+    pending: Option<(usize, usize)>,
+    children: Vec<(Edge, usize)>,
+    untried: Vec<Edge>,
+}
+
+/// What the search found out about one of Spoiler's moves from the root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveStats {
+    pub mov: StrategyMove,
+    pub visits: usize,
+    /// The colours reached on average, counting a win as `force_num_colours`.
+    pub mean_colours: f64,
+    pub most_colours: usize,
+}
+
+/// Monte Carlo tree search for Spoiler, for parameters too large to search exhaustively.
+/// Spoiler picks its moves by UCT, the Algorithm answers in the tree with the colour that did worst
+/// for Spoiler so far and, past the tree, by `rollout`. Moves follow `Game`: insertions until the board
+/// holds `max_events` events, then the barriers.
+pub struct Mcts {
+    root: ClicquedLinearAxis,
+    max_events: usize,
+    force_num_colours: usize,
+    nodes: Vec<Node>,
+    rng: StdRng,
+    pub rollout: Rollout,
+    pub exploration: f64,
+    /// Spoiler moves after which a playout stops.
+    pub max_playout: usize,
+    /// The most colours any playout reached.
+    pub most_colours: usize,
+}
+
+impl Mcts {
+    pub fn new(max_events: usize, max_clicque: usize, force_num_colours: usize, seed: u64) -> Self {
+        Self::with_axis(
+            ClicquedLinearAxis::new(max_clicque),
+            max_events,
+            force_num_colours,
+            seed,
+        )
+    }
+    pub fn with_axis(
+        root: ClicquedLinearAxis,
+        max_events: usize,
+        force_num_colours: usize,
+        seed: u64,
+    ) -> Self {
+        let mut search = Self {
+            root,
+            max_events,
+            force_num_colours,
+            nodes: vec![],
+            rng: StdRng::seed_from_u64(seed),
+            rollout: Rollout::Random,
+            exploration: std::f64::consts::SQRT_2,
+            max_playout: 4 * max_events,
+            most_colours: 0,
+        };
+        let mut axis = search.root.clone();
+        search.add_node(&mut axis, None);
+        search.most_colours = search.root.colours_used();
+        search
+    }
+    pub fn run(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            self.iterate();
+        }
+    }
+    pub fn iterations(&self) -> usize {
+        self.nodes[0].visits
+    }
+    /// Spoiler's moves from the root, the most visited first.
+    pub fn best_moves(&self) -> Vec<MoveStats> {
+        let mut moves = self.nodes[0]
+            .children
+            .iter()
+            .filter_map(|&(edge, child)| {
+                let Edge::Spoiler(mov) = edge else {
+                    return None;
+                };
+                let node = &self.nodes[child];
+                Some(MoveStats {
+                    mov,
+                    visits: node.visits,
+                    mean_colours: node.total / node.visits as f64 * self.force_num_colours as f64,
+                    most_colours: node.most_colours,
+                })
+            })
+            .collect::<Vec<_>>();
+        moves.sort_by_key(|m| std::cmp::Reverse(m.visits));
+        moves
+    }
+    /// The line the search believes in: Spoiler's most visited moves against the Algorithm's best answers.
+    pub fn principal_line(&self) -> Vec<History> {
+        let mut line = vec![];
+        let mut node = 0;
+        while let Some(&(edge, child)) = match self.nodes[node].pending {
+            None => self.nodes[node]
+                .children
+                .iter()
+                .max_by_key(|&&(_, child)| self.nodes[child].visits),
+            Some(_) => self.nodes[node]
+                .children
+                .iter()
+                .filter(|&&(_, child)| self.nodes[child].visits > 0)
+                .min_by(|&&(_, a), &&(_, b)| self.mean(a).total_cmp(&self.mean(b))),
+        } {
+            match (edge, self.nodes[node].pending) {
+                (Edge::Spoiler(mov), _) => line.extend(mov.history()),
+                (Edge::Colour(color), Some((start_index, end_index))) => {
+                    line.push(History::SegmentInsert {
+                        start_index,
+                        end_index,
+                        color,
+                    })
+                }
+                (Edge::Colour(_), None) => unreachable!(),
+            }
+            node = child;
+        }
+        line
+    }
+    fn mean(&self, node: usize) -> f64 {
+        self.nodes[node].total / self.nodes[node].visits as f64
+    }
+    fn iterate(&mut self) {
+        let mut axis = self.root.clone();
+        let mut path = vec![0];
+        let mut most = axis.colours_used();
+        let mut plies = 0;
+        loop {
+            let node = *path.last().unwrap();
+            if self.nodes[node].pending.is_none() {
+                plies += 1;
+            }
+            if !self.nodes[node].untried.is_empty() {
+                let untried = &mut self.nodes[node].untried;
+                let edge = untried.swap_remove(self.rng.random_range(0..untried.len()));
+                let pending = self.apply(&mut axis, node, edge);
+                let child = self.add_node(&mut axis, pending);
+                self.nodes[node].children.push((edge, child));
+                path.push(child);
+                most = most.max(axis.colours_used());
+                break;
+            }
+            let Some((edge, child)) = self.select(node) else {
+                break;
+            };
+            self.apply(&mut axis, node, edge);
+            path.push(child);
+            most = most.max(axis.colours_used());
+        }
+        let pending = self.nodes[*path.last().unwrap()].pending;
+        let most = self.playout(&mut axis, pending, most, plies);
+        let reward = most.min(self.force_num_colours) as f64 / self.force_num_colours as f64;
+        self.most_colours = self.most_colours.max(most);
+        for node in path {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.total += reward;
+            node.most_colours = node.most_colours.max(most);
+        }
+    }
+    /// UCT: Spoiler maximises the reward, the Algorithm minimises it.
+    fn select(&self, node: usize) -> Option<(Edge, usize)> {
+        let spoiler = self.nodes[node].pending.is_none();
+        let ln_visits = (self.nodes[node].visits.max(1) as f64).ln();
+        let score = |child: usize| {
+            let child = &self.nodes[child];
+            let visits = child.visits.max(1) as f64;
+            let mean = child.total / visits;
+            let value = if spoiler { mean } else { 1.0 - mean };
+            value + self.exploration * (ln_visits / visits).sqrt()
+        };
+        self.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .max_by(|&(_, a), &(_, b)| score(a).total_cmp(&score(b)))
+    }
+    /// Plays the edge on the axis, returns the segment the Algorithm has to colour next, if any.
+    fn apply(
+        &self,
+        axis: &mut ClicquedLinearAxis,
+        node: usize,
+        edge: Edge,
+    ) -> Option<(usize, usize)> {
+        match (edge, self.nodes[node].pending) {
+            (Edge::Spoiler(StrategyMove::Insert { start, end }), _) => Some((start, end)),
+            (Edge::Spoiler(mov), _) => {
+                axis.apply_history(mov.history().unwrap()).unwrap();
+                None
+            }
+            (Edge::Colour(color), Some((start_index, end_index))) => {
+                axis.apply_history(History::SegmentInsert {
+                    start_index,
+                    end_index,
+                    color,
+                })
+                .unwrap();
+                None
+            }
+            (Edge::Colour(_), None) => unreachable!(),
+        }
+    }
+    fn add_node(
+        &mut self,
+        axis: &mut ClicquedLinearAxis,
+        pending: Option<(usize, usize)>,
+    ) -> usize {
+        let untried = match pending {
+            _ if axis.colours_used() >= self.force_num_colours => vec![],
+            None => self
+                .spoiler_moves(axis)
+                .into_iter()
+                .map(Edge::Spoiler)
+                .collect(),
+            Some((start, end)) => colours(axis, start, end)
+                .into_iter()
+                .map(Edge::Colour)
+                .collect(),
+        };
+        self.nodes.push(Node {
+            visits: 0,
+            total: 0.0,
+            most_colours: 0,
+            pending,
+            children: vec![],
+            untried,
+        });
+        self.nodes.len() - 1
+    }
+    fn spoiler_moves(&self, axis: &mut ClicquedLinearAxis) -> Vec<StrategyMove> {
+        if axis.inner.events.len() < self.max_events {
+            return axis
+                .valid_new_segments()
+                .into_iter()
+                .map(|(start, end)| StrategyMove::Insert { start, end })
+                .collect();
+        }
+        [StrategyMove::LimitFront, StrategyMove::LimitBack]
+            .into_iter()
+            .filter(|mov| {
+                let Some(reverse) = axis.apply_history(mov.history().unwrap()) else {
+                    return false;
+                };
+                axis.apply_history(reverse);
+                true
+            })
+            .collect()
+    }
+    /// Plays on at random for Spoiler and by `rollout` for the Algorithm, returns the most colours seen.
+    fn playout(
+        &mut self,
+        axis: &mut ClicquedLinearAxis,
+        mut pending: Option<(usize, usize)>,
+        mut most: usize,
+        mut plies: usize,
+    ) -> usize {
+        while most < self.force_num_colours && plies <= self.max_playout {
+            let Some((start, end)) = pending.take() else {
+                let moves = self.spoiler_moves(axis);
+                if moves.is_empty() {
+                    break;
+                }
+                match moves[self.rng.random_range(0..moves.len())] {
+                    StrategyMove::Insert { start, end } => pending = Some((start, end)),
+                    mov => {
+                        axis.apply_history(mov.history().unwrap()).unwrap();
+                    }
+                }
+                plies += 1;
+                continue;
+            };
+            let colours = colours(axis, start, end);
+            // No colour fits: the Algorithm is out of colours.
+            if colours.is_empty() {
+                return self.force_num_colours;
+            }
+            let color = match self.rollout {
+                Rollout::Random => colours[self.rng.random_range(0..colours.len())],
+                Rollout::FirstFit => colours[0],
+            };
+            axis.apply_history(History::SegmentInsert {
+                start_index: start,
+                end_index: end,
+                color,
+            })
+            .unwrap();
+            most = most.max(axis.colours_used());
+        }
+        most
+    }
+}
+
+/// The colours the segment can take, with only the first of the colours missing from the board.
+fn colours(axis: &ClicquedLinearAxis, start: usize, end: usize) -> Vec<u8> {
+    let used = axis
+        .inner
+        .events
+        .iter()
+        .filter(|e| !e.is_pending())
+        .fold(0u64, |acc, e| acc | 1 << e.colour());
+    let mut fresh = false;
+    axis.uncollisions(start, end)
+        .into_iter()
+        .filter(|&c| used & (1 << c) != 0 || !std::mem::replace(&mut fresh, true))
+        .collect()
+}
+
+#[test]
+fn test_mcts() {
+    let run = |seed| {
+        let mut search = Mcts::new(9, 3, 5, seed);
+        search.run(2000);
+        search
+    };
+    let search = run(41);
+    assert_eq!(search.iterations(), 2000);
+    assert_eq!(search.best_moves(), run(41).best_moves());
+    assert!(search.most_colours >= 4);
+
+    let best = search.best_moves();
+    assert_eq!(
+        best.iter().map(|m| m.visits).sum::<usize>(),
+        search.iterations()
+    );
+    let mut axis = ClicquedLinearAxis::new(3);
+    for mv in search.principal_line() {
+        axis.apply_history(mv).unwrap();
+    }
+
+    let mut first_fit = Mcts::new(9, 3, 5, 41);
+    first_fit.rollout = Rollout::FirstFit;
+    first_fit.run(500);
+    assert!(first_fit.most_colours >= 3);
+}
@@ -1,15 +1,28 @@
 use event::Event;
 use history::History;
+// Replaying recorded searches (`bm_queue`) the contiguous queue is 10-35% faster,
+// whole searches (`bm_game`) differ within noise.
+#[cfg(feature = "linked-queue")]
+use linked_queue::Queue;
+#[cfg(not(feature = "linked-queue"))]
 use queue::Queue;
 
+pub mod circular;
 pub mod clicqued;
+pub mod dominance;
 pub mod event;
 pub mod game;
 pub mod history;
+pub mod jobs;
+#[cfg(any(feature = "linked-queue", test))]
+pub mod linked_queue;
+pub mod mcts;
 pub mod normalization;
+pub mod ordering;
 pub mod print;
 pub mod queue;
 pub mod strategy;
+pub mod tablebase;
 
 #[derive(Debug, Clone)]
 pub struct LinearAxis {
@@ -63,6 +76,67 @@ impl LinearAxis {
                 self.insert_event_back(start, lost);
                 Some(History::LimitBack)
             }
+            History::SegmentRecolour {
+                start_index,
+                end_index,
+                color,
+            } => self
+                .recolour_segment(start_index, end_index, color)
+                .map(|c| History::SegmentRestoreColour {
+                    start_index,
+                    end_index,
+                    color: c,
+                }),
+            History::SegmentRestoreColour {
+                start_index,
+                end_index,
+                color,
+            } => self
+                .recolour_segment(start_index, end_index, color)
+                .map(|c| History::SegmentRecolour {
+                    start_index,
+                    end_index,
+                    color: c,
+                }),
+            History::SegmentDelete {
+                start_index,
+                end_index,
+            } => self
+                .remove_segment(start_index, end_index)
+                .map(|c| History::SegmentRestore {
+                    start_index,
+                    end_index,
+                    color: c,
+                }),
+            History::SegmentRestore {
+                start_index,
+                end_index,
+                color,
+            } => {
+                self.insert_segment(start_index, end_index - 1, color)?;
+                Some(History::SegmentDelete {
+                    start_index,
+                    end_index,
+                })
+            }
+            History::SegmentTie {
+                start_index,
+                end_index,
+            } => tie_segment(&mut self.events, start_index, end_index, true).map(|()| {
+                History::SegmentUntie {
+                    start_index,
+                    end_index,
+                }
+            }),
+            History::SegmentUntie {
+                start_index,
+                end_index,
+            } => tie_segment(&mut self.events, start_index, end_index, false).map(|()| {
+                History::SegmentTie {
+                    start_index,
+                    end_index,
+                }
+            }),
         }
     }
     fn insert_segment(&mut self, start_index: usize, end_index: usize, color: u8) -> Option<()> {
@@ -83,18 +157,27 @@ impl LinearAxis {
             None
         }
     }
+    fn recolour_segment(&mut self, start_index: usize, end_index: usize, color: u8) -> Option<u8> {
+        recolour_segment(&mut self.events, start_index, end_index, color)
+    }
     fn limit_front(&mut self, max_colors: usize) -> Option<(Event, usize)> {
         let mut started = vec![false; max_colors];
         let mut found = None;
         for (i, e) in self.events.iter().enumerate() {
             if e.is_start() {
-                started[e.colour() as usize] = true
+                if !e.is_pending() {
+                    started[e.colour() as usize] = true
+                }
             } else {
                 found = Some(i);
                 break;
             }
         }
-        let found = found?;
+        let mut found = found?;
+        // Tied ends take place at one point, the barrier can't come between them.
+        while self.events.get(found)?.is_tied() {
+            found += 1;
+        }
         for _ in 0..found {
             self.front.push_back(self.events.pop_front().unwrap());
         }
@@ -108,11 +191,15 @@ impl LinearAxis {
             if e.is_start() {
                 found = Some(i);
                 break;
-            } else {
+            } else if !e.is_pending() {
                 ended[e.colour() as usize] = true
             }
         }
-        let found = found?;
+        let mut found = found?;
+        let len = self.events.len();
+        while found + 1 < len && self.events.get(len - found - 2)?.is_tied() {
+            found += 1;
+        }
         for _ in 0..found {
             self.back.push_front(self.events.pop_back().unwrap());
         }
@@ -133,6 +220,43 @@ impl LinearAxis {
     }
 }
 
+/// Gives the segment with endpoints at `start_index` and `end_index` a new colour.
+/// Returns the old one.
+fn recolour_segment(
+    events: &mut Queue<Event>,
+    start_index: usize,
+    end_index: usize,
+    color: u8,
+) -> Option<u8> {
+    let start = *events.get(start_index)?;
+    if !start.is_start() || events.get(end_index) != Some(&start.sibling()) {
+        return None;
+    }
+    *events.get_mut(start_index)? = start.with_color(color);
+    *events.get_mut(end_index)? = start.sibling().with_color(color);
+    Some(start.colour())
+}
+
+/// Ties the segment with endpoints at `start_index` and `end_index` to the next one, or unties it.
+fn tie_segment(
+    events: &mut Queue<Event>,
+    start_index: usize,
+    end_index: usize,
+    tied: bool,
+) -> Option<()> {
+    let start = *events.get(start_index)?;
+    let end = *events.get(end_index)?;
+    if !start.is_start() || end.tied(false) != start.sibling().tied(false) {
+        return None;
+    }
+    if !events.get(start_index + 1)?.is_start() || events.get(end_index + 1)?.is_start() {
+        return None;
+    }
+    *events.get_mut(start_index)? = start.tied(tied);
+    *events.get_mut(end_index)? = end.tied(tied);
+    Some(())
+}
+
 #[test]
 fn test_linear_axis_history() {
     let mut axis = LinearAxis::new();
@@ -147,6 +271,28 @@ fn test_linear_axis_history() {
             end_index: 1,
             color: 2,
         },
+        History::SegmentRecolour {
+            start_index: 0,
+            end_index: 2,
+            color: 0,
+        },
+        History::SegmentDelete {
+            start_index: 0,
+            end_index: 2,
+        },
+        History::SegmentRestore {
+            start_index: 0,
+            end_index: 2,
+            color: 0,
+        },
+        History::SegmentTie {
+            start_index: 0,
+            end_index: 2,
+        },
+        History::SegmentUntie {
+            start_index: 0,
+            end_index: 2,
+        },
         History::LimitBack,
         History::SegmentInsert {
             start_index: 0,
@@ -175,6 +321,28 @@ fn test_linear_axis_history() {
     assert_eq!(axis.events, LinearAxis::new().events);
 }
 
+#[test]
+fn test_tied_limits() {
+    use event::events_to_string;
+    let to_string = |axis: &LinearAxis| {
+        events_to_string(&axis.front)
+            + "["
+            + &events_to_string(&axis.events)
+            + "]"
+            + &events_to_string(&axis.back)
+    };
+    let mut axis = LinearAxis::from_strategy_string("[A=BCa=bc]");
+    let reverse = axis.apply_history(History::LimitFront, 3).unwrap();
+    assert_eq!(to_string(&axis), "A=BCa=[c]");
+    axis.apply_history(reverse, 3);
+    assert_eq!(to_string(&axis), "[A=BCa=bc]");
+    let mut axis = LinearAxis::from_strategy_string("[AaB=Cb=c]");
+    let reverse = axis.apply_history(History::LimitBack, 3).unwrap();
+    assert_eq!(to_string(&axis), "[Aa]Cb=c");
+    axis.apply_history(reverse, 3);
+    assert_eq!(to_string(&axis), "[AaB=Cb=c]");
+}
+
 // #[test]
 // fn test_linear_axis_history_reduction() {
 //     use normalization::strategy_normalize;
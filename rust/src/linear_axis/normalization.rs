@@ -1,12 +1,152 @@
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
 
-use super::{clicqued::ClicquedLinearAxis, event::Event, strategy::StrategyMove};
+use crate::disk_store::Record;
+
+use super::{
+    clicqued::ClicquedLinearAxis,
+    event::{events_to_string, mirrored, sort_ties, Event},
+    strategy::StrategyMove,
+};
 
 pub type CompressedState = Vec<u8>;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NormalizedState(pub Vec<Event>);
 
+/// The most events a `StateKey` holds.
+pub const MAX_KEY_EVENTS: usize = 39;
+
+/// A `NormalizedState` packed inline, the key of the transposition table.
+/// Building one, hashing it and comparing it never touches the heap.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct StateKey {
+    len: u8,
+    events: [Event; MAX_KEY_EVENTS],
+}
+
+impl StateKey {
+    fn new() -> Self {
+        Self {
+            len: 0,
+            events: [Event::default(); MAX_KEY_EVENTS],
+        }
+    }
+    fn push(&mut self, event: Event) {
+        self.events[self.len as usize] = event;
+        self.len += 1;
+    }
+    pub fn events(&self) -> &[Event] {
+        &self.events[..self.len as usize]
+    }
+    pub fn state(&self) -> NormalizedState {
+        NormalizedState(self.events().to_vec())
+    }
+    /// The events seen in a mirror, see `mirrored`.
+    pub fn mirrored(&self) -> Self {
+        let len = self.len as usize;
+        let reversed = |i: usize| self.events[len - 1 - i].sibling();
+        let mut result = Self::new();
+        for i in 0..len {
+            result.push(reversed(i).tied(i + 1 < len && reversed(i + 1).is_tied()));
+        }
+        result
+    }
+}
+
+impl From<&NormalizedState> for StateKey {
+    fn from(state: &NormalizedState) -> Self {
+        Self::from_events(state.0.iter())
+    }
+}
+
+impl StateKey {
+    /// Packs the events as they are, without renaming the colours.
+    pub fn from_events<'a>(events: impl Iterator<Item = &'a Event>) -> Self {
+        let mut key = Self::new();
+        events.for_each(|&e| key.push(e));
+        key
+    }
+}
+
+/// Remembers recent normalizations by a cheap hash of the raw board, so positions met again
+/// are recognised without renormalizing. It has a fixed number of slots, a newer board takes the slot over.
+/// In the collision-checking mode the raw boards are kept as well and every hit is checked against them.
+#[derive(Debug, Clone)]
+pub struct NormalizationCache {
+    hasher: ahash::RandomState,
+    slots: Vec<(u64, StateKey)>,
+    raw: Vec<StateKey>,
+    pub hits: usize,
+    pub collisions: usize,
+}
+
+impl NormalizationCache {
+    /// A cache of `2^bits` slots, checking for collisions when `checked`.
+    pub fn new(bits: u32, checked: bool) -> Self {
+        Self {
+            hasher: ahash::RandomState::new(),
+            slots: vec![(0, StateKey::new()); 1 << bits],
+            raw: match checked {
+                true => vec![StateKey::new(); 1 << bits],
+                false => vec![],
+            },
+            hits: 0,
+            collisions: 0,
+        }
+    }
+    pub fn normalize(&mut self, raw: &StateKey, normalize: impl FnOnce() -> StateKey) -> StateKey {
+        // Zero marks an empty slot.
+        let hash = self.hasher.hash_one(raw) | 1;
+        let slot = hash as usize & (self.slots.len() - 1);
+        if self.slots[slot].0 == hash {
+            if self.raw.is_empty() || self.raw[slot] == *raw {
+                self.hits += 1;
+                return self.slots[slot].1;
+            }
+            self.collisions += 1;
+        }
+        let key = normalize();
+        self.slots[slot] = (hash, key);
+        if !self.raw.is_empty() {
+            self.raw[slot] = *raw;
+        }
+        key
+    }
+}
+
+impl Hash for StateKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.events().hash(state);
+    }
+}
+
+impl Record for StateKey {
+    const SIZE: usize = 1 + MAX_KEY_EVENTS;
+    fn write(&self, bytes: &mut [u8]) {
+        bytes[0] = self.len;
+        for (byte, event) in bytes[1..].iter_mut().zip(self.events()) {
+            *byte = event.to_byte();
+        }
+    }
+    fn read(bytes: &[u8]) -> Self {
+        let mut key = Self::new();
+        for &byte in &bytes[1..=bytes[0] as usize] {
+            key.push(Event::from_byte(byte));
+        }
+        key
+    }
+}
+
+impl Debug for StateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StateKey({})", events_to_string(self.events()))
+    }
+}
+
 impl ClicquedLinearAxis {
     pub fn strategy_normalize(&mut self) -> (NormalizedState, bool) {
         self.normalizer.strategy_normalize(
@@ -22,28 +162,42 @@ impl ClicquedLinearAxis {
         )
     }
 
+    pub fn normalize_key(&mut self) -> StateKey {
+        let colors = self.max_colors();
+        self.normalizer
+            .normalize_key(self.inner.events.iter(), colors)
+    }
+
     pub fn strategy_normalize_with_move(
         &mut self,
         mov: StrategyMove,
     ) -> (NormalizedState, StrategyMove) {
         let (norm, flip) = self.strategy_normalize();
-        if flip {
-            (norm, self.flip_move(mov))
-        } else {
-            (norm, mov)
+        let mov = if flip { self.flip_move(mov) } else { mov };
+        // The last normalization done was of the flipped board, redo the chosen one to rename the list.
+        if !flip {
+            self.strategy_normalize_without_symmetry();
         }
+        let colors = self.max_colors();
+        (
+            norm,
+            mov.with_list(|list| self.normalizer.normalize_colours(list, colors)),
+        )
     }
 
     fn flip_move(&self, mov: StrategyMove) -> StrategyMove {
         match mov {
             StrategyMove::LimitBack => StrategyMove::LimitFront,
             StrategyMove::LimitFront => StrategyMove::LimitBack,
-            StrategyMove::Insert { start, end } => {
+            StrategyMove::Insert { start, end }
+            | StrategyMove::InsertWithList { start, end, .. }
+            | StrategyMove::InsertWithDemand { start, end, .. } => {
                 let len = self.inner.events.len();
-                StrategyMove::Insert {
-                    start: len - end,
-                    end: len - start,
-                }
+                mov.with_segment(len - end, len - start)
+            }
+            StrategyMove::Remove { start, end } => {
+                let len = self.inner.events.len();
+                mov.with_segment(len - 1 - end, len - 1 - start)
             }
         }
     }
@@ -105,32 +259,7 @@ impl StrategyNormalizer {
         compressed: &[u8],
         mov: StrategyMove,
     ) -> (NormalizedState, StrategyMove) {
-        let mut max_dis = *compressed.last().unwrap();
-        let uncompressed = decompress(&compressed[0..compressed.len() - 1]);
-        let mut queue = VecDeque::new();
-        let mut events = vec![];
-
-        // println!("{uncompressed:?}");
-
-        for (i, e) in uncompressed.iter().enumerate() {
-            if max_dis == 0 {
-                break;
-            }
-            if *e == 0 {
-                queue.push_back((i) as u8);
-                max_dis -= 1;
-            }
-        }
-
-        for e in uncompressed {
-            if e == 0 {
-                events.push(Event::new_end(queue.pop_front().unwrap()));
-            } else {
-                let c = e - 1;
-                queue.push_back(c);
-                events.push(Event::new_start(c));
-            }
-        }
+        let events = decompress_events(compressed);
 
         // println!("{:?}", events.iter().map(Event::to_char).collect::<Vec<_>>());
 
@@ -155,26 +284,70 @@ impl StrategyNormalizer {
         events: &[Event],
         max_colours: usize,
     ) -> NormalizedState {
+        let mut normalized = Vec::with_capacity(events.len());
+        self.rename(events.iter(), max_colours, |e| normalized.push(e));
+        sort_ties(&mut normalized);
+        NormalizedState(normalized)
+    }
+    /// Like `strategy_normalize_without_symmetry`, without touching the heap.
+    /// The board has to fit in a `StateKey`.
+    pub fn normalize_key<'a>(
+        &mut self,
+        events: impl Iterator<Item = &'a Event> + Clone,
+        max_colours: usize,
+    ) -> StateKey {
+        let mut normalized = StateKey::new();
+        self.rename(events, max_colours, |e| normalized.push(e));
+        let len = normalized.len as usize;
+        sort_ties(&mut normalized.events[..len]);
+        normalized
+    }
+    fn rename<'a>(
+        &mut self,
+        events: impl Iterator<Item = &'a Event> + Clone,
+        max_colours: usize,
+        mut push: impl FnMut(Event),
+    ) {
         for i in 0..max_colours {
             self.colours[i] = u8::MAX
         }
 
-        let mut normalized = Vec::with_capacity(events.len());
         let mut i = 0;
-        for e in events.iter().filter(|e| !e.is_start()) {
+        for e in events.clone().filter(|e| !e.is_start() && !e.is_pending()) {
             if self.colours[e.colour() as usize] == u8::MAX {
                 self.colours[e.colour() as usize] = i;
                 i += 1;
             }
         }
         for e in events {
+            if e.is_pending() {
+                push(*e);
+                continue;
+            }
             if self.colours[e.colour() as usize] == u8::MAX {
                 self.colours[e.colour() as usize] = i;
                 i += 1;
             }
-            normalized.push(e.with_color(self.colours[e.colour() as usize]));
+            push(e.with_color(self.colours[e.colour() as usize]));
         }
-        NormalizedState(normalized)
+    }
+    /// Renames the colours in `list` the way the last normalization renamed the board.
+    /// Colours missing from the board get the next unused names.
+    pub fn normalize_colours(&self, list: u32, max_colours: usize) -> u32 {
+        let mut fresh = self.colours[..max_colours]
+            .iter()
+            .filter(|c| **c != u8::MAX)
+            .count();
+        let mut result = 0;
+        for c in (0..max_colours).filter(|c| list & (1 << c) != 0) {
+            if self.colours[c] == u8::MAX {
+                result |= 1 << fresh;
+                fresh += 1;
+            } else {
+                result |= 1 << self.colours[c];
+            }
+        }
+        result
     }
     pub fn strategy_normalize(
         &mut self,
@@ -190,6 +363,35 @@ impl StrategyNormalizer {
     }
 }
 
+/// The events of a board packed by `normalize_compress`.
+pub fn decompress_events(compressed: &[u8]) -> Vec<Event> {
+    let mut max_dis = *compressed.last().unwrap();
+    let uncompressed = decompress(&compressed[0..compressed.len() - 1]);
+    let mut queue = VecDeque::new();
+    let mut events = vec![];
+    // Colours are numbered in order of first appearance, the ends of segments started
+    // before the board come before any other end.
+    let mut next_colour = 0;
+
+    for e in uncompressed {
+        if e == 0 && max_dis > 0 {
+            max_dis -= 1;
+            events.push(Event::new_end(next_colour));
+            next_colour += 1;
+        } else if e == 0 {
+            events.push(Event::new_end(queue.pop_front().unwrap()));
+        } else {
+            let c = e - 1;
+            if c == next_colour {
+                next_colour += 1;
+            }
+            queue.push_back(c);
+            events.push(Event::new_start(c));
+        }
+    }
+    events
+}
+
 fn decompress(data: &[u8]) -> Vec<u8> {
     let mut decompressed = Vec::with_capacity(data.len() * 2);
 
@@ -222,16 +424,56 @@ fn decompress(data: &[u8]) -> Vec<u8> {
 
 impl NormalizedState {
     pub fn flipped(&self, normalizer: &mut StrategyNormalizer, max_colours: usize) -> Self {
-        normalizer.strategy_normalize_without_symmetry(
-            &self.0.iter().map(Event::sibling).rev().collect::<Vec<_>>(),
-            max_colours,
-        )
+        normalizer.strategy_normalize_without_symmetry(&mirrored(self.0.iter()), max_colours)
     }
     pub fn colors_used(&self) -> usize {
         let mut used = vec![false; self.0.len()];
-        for e in &self.0 {
+        for e in self.0.iter().filter(|e| !e.is_pending()) {
             used[e.colour() as usize] = true;
         }
         used.into_iter().filter(|v| *v).count()
     }
 }
+
+#[test]
+fn test_state_key() {
+    use super::{event::events_from_string, game::GameAxis};
+    use std::collections::hash_map::DefaultHasher;
+
+    let hash = |key: &StateKey| {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    };
+    let mut axis = ClicquedLinearAxis::from_strategy_string("A[BaC=Dbc=d]", 3);
+    let state = axis.strategy_normalize_without_symmetry();
+    let key = axis.key();
+    assert_eq!(key.state(), state);
+    assert_eq!(key, StateKey::from(&state));
+    assert_eq!(hash(&key), hash(&StateKey::from(&state)));
+    assert_eq!(
+        key.mirrored().state(),
+        NormalizedState(mirrored(state.0.iter()))
+    );
+    assert_eq!(axis.flipped_key(&key).state(), axis.flipped(&state));
+    assert_ne!(
+        key,
+        StateKey::from(&NormalizedState(events_from_string("aBCbc")))
+    );
+}
+
+#[test]
+fn test_normalization_cache() {
+    use super::event::events_from_string;
+
+    // A single slot, every board takes it over.
+    let mut cache = NormalizationCache::new(0, true);
+    let mut normalizer = StrategyNormalizer::new();
+    for board in ["BbAa", "AaBb", "BbAa", "BbAa"] {
+        let events = events_from_string(board);
+        let raw = StateKey::from_events(events.iter());
+        let key = cache.normalize(&raw, || normalizer.normalize_key(events.iter(), 5));
+        assert_eq!(key.state(), NormalizedState(events_from_string("AaBb")));
+    }
+    assert_eq!(cache.hits, 1);
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use crate::hash::GlobalRandomState;
+
+use super::strategy::StrategyMove;
+
+/// The order Spoiler's insertions are tried in.
+/// The first winning one cuts off the rest, so a good order expands fewer positions.
+/// Positions met again while still being searched count as lost, so an order can also turn up wins another misses.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum MoveOrdering {
+    /// As the axis lists them, from the left.
+    #[default]
+    Index,
+    /// Fewest answers of the Algorithm first, like `preferable_order` in `simple_state`.
+    FewestResponses,
+    /// The move that last won on a board of the same size first, then the ones that won most often.
+    Killer,
+    /// Moves with the most answers already known to be won first, the ones with a known loss last.
+    PriorWin,
+}
+
+/// What the killer heuristic remembers across siblings.
+#[derive(Debug, Default, Clone)]
+pub struct MoveHistory {
+    /// The last winning move, by the number of events on the board.
+    killers: Vec<Option<StrategyMove>>,
+    wins: HashMap<StrategyMove, usize, GlobalRandomState>,
+}
+
+impl MoveHistory {
+    pub fn record(&mut self, events: usize, mov: StrategyMove) {
+        if self.killers.len() <= events {
+            self.killers.resize(events + 1, None);
+        }
+        self.killers[events] = Some(mov);
+        *self.wins.entry(mov).or_default() += 1;
+    }
+    /// Higher is tried first.
+    pub fn score(&self, events: usize, mov: StrategyMove) -> (bool, usize) {
+        (
+            self.killers.get(events) == Some(&Some(mov)),
+            self.wins.get(&mov).copied().unwrap_or_default(),
+        )
+    }
+}
+
+#[test]
+fn test_move_history() {
+    let mut history = MoveHistory::default();
+    let a = StrategyMove::Insert { start: 0, end: 1 };
+    let b = StrategyMove::Insert { start: 1, end: 2 };
+    history.record(4, a);
+    history.record(2, a);
+    history.record(4, b);
+    assert_eq!(history.score(4, b), (true, 1));
+    assert_eq!(history.score(4, a), (false, 2));
+    assert_eq!(history.score(2, a), (true, 2));
+    assert_eq!(history.score(6, b), (false, 1));
+}
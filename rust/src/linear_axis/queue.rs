@@ -1,43 +1,27 @@
-use std::{fmt::Debug, iter::zip};
+use std::fmt::Debug;
 
+/// A sequence kept contiguous in the middle of a buffer with room on both sides.
+/// Boards are a few dozen events, so indexing is a slice access
+/// and inserting or removing moves the shorter side over by one.
 #[derive(Debug, Clone)]
 pub struct Queue<T> {
-    nodes: Vec<Node<T>>,
-    first: usize,
-    last: usize,
-    free: usize,
+    items: Vec<T>,
+    front: usize,
     len: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Node<T> {
-    next: usize,
-    prev: usize,
-    val: T,
-}
-
-impl<T: Default> Node<T> {
-    pub fn default_value(next: usize, prev: usize) -> Self {
-        Self::new(next, prev, T::default())
-    }
-    pub fn new(next: usize, prev: usize, val: T) -> Self {
-        Self { next, prev, val }
-    }
-}
+pub type QueueIterator<'a, T> = std::slice::Iter<'a, T>;
 
 impl<T: Default + Debug + Clone> Queue<T> {
     pub fn new() -> Self {
         Self::with_capacity(30)
     }
     pub fn with_capacity(capacity: usize) -> Self {
+        let size = capacity.max(2);
         Self {
-            nodes: (0..capacity)
-                .map(|i| Node::default_value((i + 1) % capacity, 0))
-                .collect(),
-            first: 0,
-            last: 0,
+            items: vec![T::default(); size],
+            front: size / 2,
             len: 0,
-            free: 0,
         }
     }
     pub fn len(&self) -> usize {
@@ -46,53 +30,44 @@ impl<T: Default + Debug + Clone> Queue<T> {
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
-    fn grow(&mut self) {
-        let mut new = Self::with_capacity(self.nodes.len() * 2 + 1);
-        new.extend(self.into_iter().cloned());
-        *self = new;
+    fn as_slice(&self) -> &[T] {
+        &self.items[self.front..self.front + self.len]
     }
-    pub fn push_back(&mut self, val: T) {
-        if self.len + 1 == self.nodes.len() {
-            self.grow();
+    /// Moves the items back to the middle, growing the buffer when less than half of it is free.
+    fn recentre(&mut self) {
+        if (self.len + 2) * 2 > self.items.len() {
+            self.items.resize(self.items.len() * 2 + 2, T::default());
         }
-        if self.len == 0 {
-            self.first = self.free;
+        let front = (self.items.len() - self.len) / 2;
+        if front < self.front {
+            self.items[front..self.front + self.len].rotate_left(self.front - front);
         } else {
-            self.nodes[self.last].next = self.free;
+            self.items[self.front..front + self.len].rotate_right(front - self.front);
+        }
+        self.front = front;
+    }
+    pub fn push_back(&mut self, val: T) {
+        if self.front + self.len == self.items.len() {
+            self.recentre();
         }
-        self.nodes[self.free].val = val;
-        self.nodes[self.free].prev = self.last;
-        self.last = self.free;
-        self.free = self.nodes[self.free].next;
+        self.items[self.front + self.len] = val;
         self.len += 1;
     }
     pub fn push_front(&mut self, val: T) {
-        if self.len + 1 == self.nodes.len() {
-            self.grow();
+        if self.front == 0 {
+            self.recentre();
         }
-        if self.len == 0 {
-            self.last = self.free;
-        } else {
-            self.nodes[self.first].prev = self.free;
-        }
-        let next_free = self.nodes[self.free].next;
-        self.nodes[self.free].val = val;
-        self.nodes[self.free].next = self.first;
-        self.first = self.free;
-        self.free = next_free;
+        self.front -= 1;
+        self.items[self.front] = val;
         self.len += 1;
     }
     pub fn pop_front(&mut self) -> Option<T> {
         if self.len == 0 {
             return None;
         }
+        let val = std::mem::take(&mut self.items[self.front]);
+        self.front += 1;
         self.len -= 1;
-        let mut val = T::default();
-        std::mem::swap(&mut val, &mut self.nodes[self.first].val);
-        let next_free = self.nodes[self.first].next;
-        self.nodes[self.first].next = self.free;
-        self.free = self.first;
-        self.first = next_free;
         Some(val)
     }
     pub fn pop_back(&mut self) -> Option<T> {
@@ -100,55 +75,40 @@ impl<T: Default + Debug + Clone> Queue<T> {
             return None;
         }
         self.len -= 1;
-        let mut val = T::default();
-        std::mem::swap(&mut val, &mut self.nodes[self.last].val);
-        self.nodes[self.last].next = self.free;
-        self.free = self.last;
-        self.last = self.nodes[self.last].prev;
-        Some(val)
+        Some(std::mem::take(&mut self.items[self.front + self.len]))
     }
     pub fn remove_at_index(&mut self, index: usize) -> Option<T> {
         if index >= self.len {
             return None;
         }
-        if index == 0 {
-            return self.pop_front();
-        }
-        if index == self.len - 1 {
-            return self.pop_back();
+        let at = self.front + index;
+        let val = std::mem::take(&mut self.items[at]);
+        if index < self.len / 2 {
+            self.items[self.front..=at].rotate_right(1);
+            self.front += 1;
+        } else {
+            self.items[at..self.front + self.len].rotate_left(1);
         }
-        let found = self.get_ith(index);
-        let Node { next, prev, .. } = self.nodes[found];
-        self.nodes[prev].next = next;
-        self.nodes[next].prev = prev;
-        self.nodes[found].next = self.free;
-        self.free = found;
         self.len -= 1;
-        Some(self.nodes[found].val.clone())
+        Some(val)
     }
     pub fn insert_at_index(&mut self, index: usize, val: T) -> Option<()> {
-        if index == 0 {
-            self.push_front(val);
-            return Some(());
-        }
-        if index == self.len {
-            self.push_back(val);
-            return Some(());
-        }
         if index > self.len {
             return None;
         }
-        let found = self.get_ith(index);
-        let next_free = self.nodes[self.free].next;
-        let prv = self.nodes[found].prev;
-        self.nodes[self.free] = Node {
-            next: found,
-            prev: prv,
-            val,
-        };
-        self.nodes[found].prev = self.free;
-        self.nodes[prv].next = self.free;
-        self.free = next_free;
+        if index < self.len / 2 {
+            if self.front == 0 {
+                self.recentre();
+            }
+            self.front -= 1;
+            self.items[self.front..=self.front + index].rotate_left(1);
+        } else {
+            if self.front + self.len == self.items.len() {
+                self.recentre();
+            }
+            self.items[self.front + index..=self.front + self.len].rotate_right(1);
+        }
+        self.items[self.front + index] = val;
         self.len += 1;
         Some(())
     }
@@ -157,85 +117,31 @@ impl<T: Default + Debug + Clone> Queue<T> {
             self.push_back(i);
         }
     }
-    pub fn iter<'a>(&'a self) -> QueueIterator<'a, T> {
-        self.into_iter()
+    pub fn iter(&self) -> QueueIterator<'_, T> {
+        self.as_slice().iter()
     }
-    pub fn get<'a>(&'a self, index: usize) -> Option<&'a T> {
-        self.iter().skip(index).next()
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
     }
-    fn get_ith(&self, i: usize) -> usize {
-        if i > self.len / 2 {
-            return self.get_ith_back(self.len - i - 1);
-        }
-        let mut result = self.first;
-        for _ in 0..i {
-            result = self.nodes[result].next;
-        }
-        result
-    }
-    fn get_ith_back(&self, i: usize) -> usize {
-        let mut result = self.last;
-        for _ in 0..i {
-            result = self.nodes[result].prev;
-        }
-        result
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.items[self.front..self.front + self.len].get_mut(index)
     }
 }
 
 impl<T: PartialEq> PartialEq for Queue<T> {
     fn eq(&self, other: &Self) -> bool {
-        if self.len != other.len {
-            return false;
-        }
-        zip(self.into_iter(), other.into_iter()).all(|(a, b)| a == b)
+        self.items[self.front..self.front + self.len]
+            == other.items[other.front..other.front + other.len]
     }
 }
 
-impl<'a, T> IntoIterator for &'a Queue<T> {
+impl<'a, T: Default + Debug + Clone> IntoIterator for &'a Queue<T> {
     type Item = &'a T;
 
     type IntoIter = QueueIterator<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        QueueIterator {
-            queue: self,
-            cur_front: self.first,
-            cur_back: self.last,
-            done: 0,
-        }
-    }
-}
-
-pub struct QueueIterator<'a, T> {
-    queue: &'a Queue<T>,
-    cur_front: usize,
-    cur_back: usize,
-    done: usize,
-}
-
-impl<'a, T> Iterator for QueueIterator<'a, T> {
-    type Item = &'a T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.done == self.queue.len {
-            return None;
-        }
-        let node = &self.queue.nodes[self.cur_front];
-        self.cur_front = node.next;
-        self.done += 1;
-        Some(&node.val)
-    }
-}
-
-impl<'a, T> DoubleEndedIterator for QueueIterator<'a, T> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        if self.done == self.queue.len {
-            return None;
-        }
-        let node = &self.queue.nodes[self.cur_back];
-        self.cur_back = node.prev;
-        self.done += 1;
-        Some(&node.val)
+        self.iter()
     }
 }
 
@@ -259,403 +165,64 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
-    #[test]
-    fn test_new() {
-        let q: Queue<i32> = Queue::new();
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_with_capacity() {
-        let q: Queue<i32> = Queue::with_capacity(10);
-        assert_eq!(q.nodes.len(), 10);
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_push_back_empty() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        assert_eq!(q.len(), 1);
-        assert!(!q.is_empty());
-        assert_eq!(q.first, 0);
-        assert_eq!(q.last, 0);
-        assert_eq!(q.get(0), Some(&1));
-    }
-
-    #[test]
-    fn test_push_back_non_empty() {
-        let mut q = Queue::with_capacity(5);
-        q.push_back(1);
-        q.push_back(2);
-        assert_eq!(q.len(), 2);
-        assert_eq!(q.first, 0);
-        assert_eq!(q.last, 1);
-        dbg!(&q);
-        assert_eq!(q.get(1), Some(&2));
-        assert_eq!(q.get(0), Some(&1));
-    }
-
-    // #[test]
-    // #[should_panic]
-    // fn test_push_back_overflow() {
-    //     let mut q = Queue::with_capacity(1);
-    //     q.push_back(1);
-    //     q.push_back(2);
-    // }
-
-    #[test]
-    fn test_push_front_empty() {
-        let mut q = Queue::new();
-        q.push_front(1);
-        assert_eq!(q.len(), 1);
-        assert!(!q.is_empty());
-        assert_eq!(q.first, 0);
-        assert_eq!(q.last, 0);
-        assert_eq!(q.get(0), Some(&1));
-    }
-
-    #[test]
-    fn test_push_front_non_empty() {
-        let mut q = Queue::with_capacity(5);
-        q.push_front(2);
-        q.push_front(1);
-        assert_eq!(q.len(), 2);
-        assert_eq!(q.first, 1);
-        assert_eq!(q.last, 0);
-        assert_eq!(q.get(0), Some(&1));
-        assert_eq!(q.get(1), Some(&2));
-    }
-
-    // #[test]
-    // #[should_panic]
-    // fn test_push_front_overflow() {
-    //     let mut q = Queue::with_capacity(1);
-    //     q.push_front(1);
-    //     q.push_front(2);
-    // }
-
-    #[test]
-    fn test_pop_front_empty() {
-        let mut q: Queue<i32> = Queue::new();
-        assert_eq!(q.pop_front(), None);
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_pop_front_single() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        assert_eq!(q.pop_front(), Some(1));
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_pop_front_multiple() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        q.push_back(2);
-        assert_eq!(q.pop_front(), Some(1));
-        assert_eq!(q.len(), 1);
-        assert_eq!(q.pop_front(), Some(2));
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_pop_back_empty() {
-        let mut q: Queue<i32> = Queue::new();
-        assert_eq!(q.pop_back(), None);
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_pop_back_single() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        assert_eq!(q.pop_back(), Some(1));
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_pop_back_multiple() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        q.push_back(2);
-        assert_eq!(q.pop_back(), Some(2));
-        assert_eq!(q.len(), 1);
-        assert_eq!(q.pop_back(), Some(1));
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_extend_empty() {
-        let mut q: Queue<i32> = Queue::new();
-        q.extend(vec![]);
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
+    use std::collections::VecDeque;
 
-    #[test]
-    fn test_extend_single() {
-        let mut q: Queue<i32> = Queue::new();
-        q.extend(vec![1]);
-        assert_eq!(q.len(), 1);
-        assert_eq!(q.get(0), Some(&1));
-    }
-
-    #[test]
-    fn test_extend_multiple() {
-        let mut q: Queue<i32> = Queue::new();
-        q.extend(vec![1, 2, 3]);
-        assert_eq!(q.len(), 3);
-        assert_eq!(q.get(0), Some(&1));
-        assert_eq!(q.get(1), Some(&2));
-        assert_eq!(q.get(2), Some(&3));
-    }
-
-    #[test]
-    fn test_iter_empty() {
-        let q: Queue<i32> = Queue::new();
-        let mut iter = q.iter();
-        assert_eq!(iter.next(), None);
-    }
-
-    #[test]
-    fn test_iter_single() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        let mut iter = q.iter();
-        assert_eq!(iter.next(), Some(&1));
-        assert_eq!(iter.next(), None);
-    }
-
-    #[test]
-    fn test_iter_multiple() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        q.push_back(2);
-        let mut iter = q.iter();
-        assert_eq!(iter.next(), Some(&1));
-        assert_eq!(iter.next(), Some(&2));
-        assert_eq!(iter.next(), None);
-    }
-
-    #[test]
-    fn test_into_iter_empty() {
-        let q: Queue<i32> = Queue::new();
-        let mut iter = q.into_iter();
-        assert_eq!(iter.next(), None);
-    }
-
-    #[test]
-    fn test_into_iter_single() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        let mut iter = q.into_iter();
-        assert_eq!(iter.next(), Some(&1));
-        assert_eq!(iter.next(), None);
-    }
+    use rand::{rngs::StdRng, Rng, SeedableRng};
 
-    #[test]
-    fn test_into_iter_multiple() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        q.push_back(2);
-        let mut iter = q.into_iter();
-        assert_eq!(iter.next(), Some(&1));
-        assert_eq!(iter.next(), Some(&2));
-        assert_eq!(iter.next(), None);
-    }
-
-    #[test]
-    fn test_get_empty() {
-        let q: Queue<i32> = Queue::new();
-        assert_eq!(q.get(0), None);
-    }
-
-    #[test]
-    fn test_get_single() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        assert_eq!(q.get(0), Some(&1));
-        assert_eq!(q.get(1), None);
-    }
-
-    #[test]
-    fn test_get_multiple() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        q.push_back(2);
-        q.push_back(3);
-        assert_eq!(q.get(0), Some(&1));
-        assert_eq!(q.get(1), Some(&2));
-        assert_eq!(q.get(2), Some(&3));
-        assert_eq!(q.get(3), None);
-    }
-
-    #[test]
-    fn test_partial_eq_empty() {
-        let q1: Queue<i32> = Queue::new();
-        let q2: Queue<i32> = Queue::new();
-        assert_eq!(q1, q2);
-    }
-
-    #[test]
-    fn test_partial_eq_single_equal() {
-        let mut q1 = Queue::new();
-        q1.push_back(1);
-        let mut q2 = Queue::new();
-        q2.push_back(1);
-        assert_eq!(q1, q2);
-    }
-
-    #[test]
-    fn test_partial_eq_single_not_equal() {
-        let mut q1 = Queue::new();
-        q1.push_back(1);
-        let mut q2 = Queue::new();
-        q2.push_back(2);
-        assert_ne!(q1, q2);
-    }
-
-    #[test]
-    fn test_partial_eq_multiple_equal() {
-        let mut q1 = Queue::new();
-        q1.extend(vec![1, 2, 3]);
-        let mut q2 = Queue::new();
-        q2.extend(vec![1, 2, 3]);
-        assert_eq!(q1, q2);
-    }
-
-    #[test]
-    fn test_partial_eq_multiple_not_equal_length() {
-        let mut q1 = Queue::new();
-        q1.extend(vec![1, 2]);
-        let mut q2 = Queue::new();
-        q2.extend(vec![1, 2, 3]);
-        assert_ne!(q1, q2);
-    }
-
-    #[test]
-    fn test_partial_eq_multiple_not_equal_elements() {
-        let mut q1 = Queue::new();
-        q1.extend(vec![1, 2, 3]);
-        let mut q2 = Queue::new();
-        q2.extend(vec![1, 4, 3]);
-        assert_ne!(q1, q2);
-    }
-
-    #[test]
-    fn test_from_iterator_empty() {
-        let q: Queue<i32> = vec![].into_iter().collect();
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_from_iterator_single() {
-        let q: Queue<i32> = vec![1].into_iter().collect();
-        assert_eq!(q.len(), 1);
-        assert_eq!(q.get(0), Some(&1));
-    }
-
-    #[test]
-    fn test_from_iterator_multiple() {
-        let q: Queue<i32> = vec![1, 2, 3].into_iter().collect();
-        assert_eq!(q.len(), 3);
-        assert_eq!(q.get(0), Some(&1));
-        assert_eq!(q.get(1), Some(&2));
-        assert_eq!(q.get(2), Some(&3));
-    }
-
-    #[test]
-    fn test_from_vec_empty() {
-        let q: Queue<i32> = Vec::new().into();
-        assert_eq!(q.len(), 0);
-        assert!(q.is_empty());
-    }
-
-    #[test]
-    fn test_from_vec_single() {
-        let q: Queue<i32> = vec![1].into();
-        assert_eq!(q.len(), 1);
-        assert_eq!(q.get(0), Some(&1));
-    }
-
-    #[test]
-    fn test_from_vec_multiple() {
-        let q: Queue<i32> = vec![1, 2, 3].into();
-        assert_eq!(q.len(), 3);
-        assert_eq!(q.get(0), Some(&1));
-        assert_eq!(q.get(1), Some(&2));
-        assert_eq!(q.get(2), Some(&3));
-    }
-
-    #[test]
-    fn test_double_ended_iterator_empty() {
-        let q: Queue<i32> = Queue::new();
-        let mut iter = q.iter();
-        assert_eq!(iter.next_back(), None);
-    }
-
-    #[test]
-    fn test_double_ended_iterator_single() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        let mut iter = q.iter();
-        assert_eq!(iter.next_back(), Some(&1));
-        assert_eq!(iter.next_back(), None);
-    }
-
-    #[test]
-    fn test_double_ended_iterator_multiple() {
-        let mut q = Queue::new();
-        q.push_back(1);
-        q.push_back(2);
-        q.push_back(3);
-        let mut iter = q.iter();
-        assert_eq!(iter.next_back(), Some(&3));
-        assert_eq!(iter.next(), Some(&1));
-        assert_eq!(iter.next_back(), Some(&2));
-        assert_eq!(iter.next(), None);
-        assert_eq!(iter.next_back(), None);
-    }
-
-    #[test]
-    fn test_pop_front_and_push_back_interleaved() {
-        let mut q = Queue::with_capacity(5);
-        q.push_back(1);
-        q.push_back(2);
-        assert_eq!(q.pop_front(), Some(1));
-        q.push_back(3);
-        assert_eq!(q.pop_front(), Some(2));
-        q.push_back(4);
-        assert_eq!(q.pop_front(), Some(3));
-        assert_eq!(q.pop_front(), Some(4));
-        assert!(q.is_empty());
-    }
+    use super::*;
 
     #[test]
-    fn test_pop_back_and_push_front_interleaved() {
-        let mut q = Queue::with_capacity(5);
-        q.push_front(1);
-        q.push_front(2);
-        assert_eq!(q.pop_back(), Some(1));
-        q.push_front(3);
-        assert_eq!(q.pop_back(), Some(2));
-        q.push_front(4);
-        assert_eq!(q.pop_back(), Some(3));
-        assert_eq!(q.pop_back(), Some(4));
-        assert!(q.is_empty());
+    fn test_grow_both_ends() {
+        let mut q = Queue::with_capacity(0);
+        for i in 0..20 {
+            q.push_back(i);
+            q.push_front(-i);
+        }
+        assert_eq!(q.len(), 40);
+        assert_eq!(q.get(0), Some(&-19));
+        assert_eq!(q.get(39), Some(&19));
+        assert!(q.iter().copied().eq((-19..=0).chain(0..20)));
+    }
+
+    #[test]
+    fn test_insert_remove_middle() {
+        let mut q: Queue<i32> = vec![1, 2, 4, 5].into();
+        q.insert_at_index(2, 3).unwrap();
+        q.insert_at_index(0, 0).unwrap();
+        q.insert_at_index(6, 6).unwrap();
+        assert_eq!(q.insert_at_index(8, 8), None);
+        assert_eq!(q, (0..7).into());
+        assert_eq!(q.remove_at_index(1), Some(1));
+        assert_eq!(q.remove_at_index(4), Some(5));
+        assert_eq!(q.remove_at_index(5), None);
+        assert_eq!(q, vec![0, 2, 3, 4, 6].into());
+    }
+
+    #[test]
+    fn test_against_vec_deque() {
+        let mut rng = StdRng::seed_from_u64(38);
+        let mut q = Queue::with_capacity(4);
+        let mut expected = VecDeque::new();
+        for i in 0..10_000 {
+            let index = rng.random_range(0..=expected.len());
+            match rng.random_range(0..6) {
+                0 => {
+                    q.push_front(i);
+                    expected.push_front(i);
+                }
+                1 => {
+                    q.push_back(i);
+                    expected.push_back(i);
+                }
+                2 => assert_eq!(q.pop_front(), expected.pop_front()),
+                3 => assert_eq!(q.pop_back(), expected.pop_back()),
+                4 => {
+                    q.insert_at_index(index, i).unwrap();
+                    expected.insert(index, i);
+                }
+                _ => assert_eq!(q.remove_at_index(index), expected.remove(index)),
+            }
+            assert!(q.iter().eq(expected.iter()));
+        }
     }
 }
@@ -1,15 +1,44 @@
-use std::{cell::RefCell, collections::HashMap, hash::Hash, io::Write};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+    io::{self, BufRead, Write},
+};
 
 use crate::hash::GlobalRandomState;
 
 use super::{
-    clicqued::ClicquedLinearAxis, event::Event, history::History, normalization::NormalizedState,
+    clicqued::ClicquedLinearAxis,
+    event::{events_from_string, events_to_string, Event},
+    game::GameAxis,
+    history::History,
+    normalization::NormalizedState,
     LinearAxis,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StrategyMove {
-    Insert { start: usize, end: usize },
+    Insert {
+        start: usize,
+        end: usize,
+    },
+    /// An insertion in the list colouring game: the Algorithm has to pick one of the colours in `list`.
+    InsertWithList {
+        start: usize,
+        end: usize,
+        list: u32,
+    },
+    /// An insertion in the multicolouring game: the Algorithm has to give the segment `demand` colours.
+    InsertWithDemand {
+        start: usize,
+        end: usize,
+        demand: usize,
+    },
+    /// Spoiler deletes the segment with endpoints at `start` and `end`.
+    Remove {
+        start: usize,
+        end: usize,
+    },
     LimitFront,
     LimitBack,
 }
@@ -18,17 +47,104 @@ impl StrategyMove {
     pub fn string(&self, offset: usize) -> String {
         match self {
             StrategyMove::Insert { start, end } => format!("{} {}", start + offset, end + offset),
+            StrategyMove::InsertWithList { start, end, list } => format!(
+                "{} {} {}",
+                start + offset,
+                end + offset,
+                (0..32u8)
+                    .filter(|c| list & (1 << c) != 0)
+                    .map(|c| Event::new_start(c).to_char())
+                    .collect::<String>()
+            ),
+            StrategyMove::Remove { start, end } => format!("- {} {}", start + offset, end + offset),
+            StrategyMove::InsertWithDemand { start, end, demand } => {
+                format!("{} {} {}", start + offset, end + offset, demand)
+            }
             StrategyMove::LimitFront => ">".to_string(),
             StrategyMove::LimitBack => "<".to_string(),
         }
     }
+    /// Reads back what `string` wrote.
+    pub fn from_string(string: &str, offset: usize) -> Option<Self> {
+        let index = |s: &str| s.parse::<usize>().ok()?.checked_sub(offset);
+        Some(match string.split(' ').collect::<Vec<_>>()[..] {
+            [">"] => StrategyMove::LimitFront,
+            ["<"] => StrategyMove::LimitBack,
+            ["-", start, end] => StrategyMove::Remove {
+                start: index(start)?,
+                end: index(end)?,
+            },
+            [start, end] => StrategyMove::Insert {
+                start: index(start)?,
+                end: index(end)?,
+            },
+            [start, end, extra] => match extra.parse() {
+                Ok(demand) => StrategyMove::InsertWithDemand {
+                    start: index(start)?,
+                    end: index(end)?,
+                    demand,
+                },
+                Err(_) => StrategyMove::InsertWithList {
+                    start: index(start)?,
+                    end: index(end)?,
+                    list: extra.chars().map(Event::from_char).try_fold(0, |acc, e| {
+                        Some(
+                            acc | 1u32
+                                .checked_shl(e.colour() as u32)
+                                .filter(|_| !e.is_pending())?,
+                        )
+                    })?,
+                },
+            },
+            _ => return None,
+        })
+    }
+    /// The move on the board seen in a mirror, the board having `events` events.
+    /// `None` for tied segments, which cannot be mirrored.
+    pub fn mirrored(self, events: usize) -> Option<Self> {
+        match self {
+            Self::Insert { start, end } | Self::InsertWithList { start, end, .. } => {
+                Some(self.with_segment(events - end, events - start))
+            }
+            Self::Remove { start, end } => Some(Self::Remove {
+                start: events - 1 - end,
+                end: events - 1 - start,
+            }),
+            Self::LimitFront => Some(Self::LimitBack),
+            Self::LimitBack => Some(Self::LimitFront),
+            Self::InsertWithDemand { .. } => None,
+        }
+    }
     pub fn history(&self) -> Option<History> {
         match self {
             Self::LimitBack => Some(History::LimitBack),
             Self::LimitFront => Some(History::LimitFront),
+            &Self::Remove { start, end } => Some(History::SegmentDelete {
+                start_index: start,
+                end_index: end,
+            }),
             _ => None,
         }
     }
+    pub fn with_segment(self, start: usize, end: usize) -> Self {
+        match self {
+            Self::Insert { .. } => Self::Insert { start, end },
+            Self::InsertWithList { list, .. } => Self::InsertWithList { start, end, list },
+            Self::InsertWithDemand { demand, .. } => Self::InsertWithDemand { start, end, demand },
+            Self::Remove { .. } => Self::Remove { start, end },
+            limit => limit,
+        }
+    }
+    pub fn with_list(self, f: impl FnOnce(u32) -> u32) -> Self {
+        match self {
+            Self::InsertWithList { start, end, list } => Self::InsertWithList {
+                start,
+                end,
+                list: f(list),
+            },
+            mov => mov,
+        }
+    }
 }
 
 impl History {
@@ -36,6 +152,13 @@ impl History {
         match self {
             History::LimitFront => Some(StrategyMove::LimitFront),
             History::LimitBack => Some(StrategyMove::LimitBack),
+            &History::SegmentDelete {
+                start_index,
+                end_index,
+            } => Some(StrategyMove::Remove {
+                start: start_index,
+                end: end_index,
+            }),
             _ => None,
         }
     }
@@ -49,6 +172,10 @@ pub struct StrategyState {
 }
 
 impl StrategyState {
+    /// The state and `mov` from it, as a line of a strategy file.
+    pub fn strategy_line(&self, mov: StrategyMove) -> String {
+        format!("{} {}", self.to_string(), mov.string(self.front.len()))
+    }
     pub fn without_boundaries(&self) -> Vec<Event> {
         [&self.front, &self.actual, &self.back]
             .into_iter()
@@ -68,7 +195,7 @@ impl StrategyState {
     pub fn from(&NormalizedState(ref state): &NormalizedState, max_colors: usize) -> Self {
         let mut started = vec![false; max_colors];
         let mut front = vec![];
-        for e in state {
+        for e in state.iter().filter(|e| !e.is_pending()) {
             if e.is_start() {
                 started[e.colour() as usize] = true;
             } else if !started[e.colour() as usize] {
@@ -78,7 +205,7 @@ impl StrategyState {
         }
         let mut finished = vec![false; max_colors];
         let mut back = vec![];
-        for e in state.iter().rev() {
+        for e in state.iter().rev().filter(|e| !e.is_pending()) {
             if !e.is_start() {
                 finished[e.colour() as usize] = true;
             } else if !finished[e.colour() as usize] {
@@ -93,15 +220,381 @@ impl StrategyState {
             back,
         }
     }
+    /// On a circle there are no barriers, so the state is just the events.
+    pub fn circular(NormalizedState(state): &NormalizedState) -> Self {
+        Self {
+            front: vec![],
+            actual: state.clone(),
+            back: vec![],
+        }
+    }
     pub fn to_string(&self) -> String {
-        self.front.iter().map(Event::to_char).collect::<String>()
+        events_to_string(&self.front)
             + "["
-            + &self.actual.iter().map(Event::to_char).collect::<String>()
+            + &events_to_string(&self.actual)
             + "]"
-            + &self.back.iter().map(Event::to_char).collect::<String>()
+            + &events_to_string(&self.back)
+    }
+    /// Reads back what `to_string` wrote.
+    pub fn from_string(string: &str) -> Option<Self> {
+        let (front, rest) = string.split_once('[')?;
+        let (actual, back) = rest.split_once(']')?;
+        Some(Self {
+            front: events_from_string(front),
+            actual: events_from_string(actual),
+            back: events_from_string(back),
+        })
+    }
+    /// The state and the move from it, from a line of a strategy file.
+    pub fn from_strategy_line(line: &str) -> Option<(Self, StrategyMove)> {
+        let (state, mov) = line.split_once(' ')?;
+        let state = Self::from_string(state)?;
+        let mov = StrategyMove::from_string(mov, state.front.len())?;
+        Some((state, mov))
+    }
+}
+
+/// A strategy file read back: Spoiler's move in every position the strategy reaches.
+#[derive(Debug, Clone, Default)]
+pub struct Strategy {
+    pub max_clicque: usize,
+    pub force_num_colours: usize,
+    pub moves: HashMap<StrategyState, StrategyMove, GlobalRandomState>,
+}
+
+impl Strategy {
+    pub fn read(r: &mut impl BufRead) -> io::Result<Self> {
+        let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, line.to_string());
+        let mut lines = r.lines();
+        let header = lines.next().unwrap_or(Ok(String::new()))?;
+        let Some((Ok(max_clicque), Ok(force_num_colours))) = header
+            .split_once(' ')
+            .map(|(clicque, force)| (clicque.parse(), force.parse()))
+        else {
+            return Err(invalid(&header));
+        };
+        let mut moves = HashMap::default();
+        for line in lines {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let (state, mov) =
+                StrategyState::from_strategy_line(&line).ok_or_else(|| invalid(&line))?;
+            moves.insert(state, mov);
+        }
+        Ok(Self {
+            max_clicque,
+            force_num_colours,
+            moves,
+        })
+    }
+
+    /// Writes the strategy file, its lines sorted.
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, "{} {}", self.max_clicque, self.force_num_colours)?;
+        let mut lines = self.lines();
+        lines.sort();
+        for line in lines {
+            writeln!(w, "{line}")?;
+        }
+        Ok(())
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.moves
+            .iter()
+            .map(|(state, &mov)| state.strategy_line(mov))
+            .collect()
+    }
+
+    /// Spoiler's move on the board as it is laid out, whether the strategy has the position or its mirror image.
+    pub fn move_on(&self, axis: &mut ClicquedLinearAxis) -> Option<StrategyMove> {
+        self.find(axis).map(|(_, mov)| mov)
+    }
+
+    /// The position as the strategy has it, with the move in the orientation of `axis`.
+    /// Positions played with tied segments are only found as they are laid out.
+    fn find(&self, axis: &mut ClicquedLinearAxis) -> Option<(&StrategyState, StrategyMove)> {
+        let normalized = axis.normalize();
+        if let Some((state, &mov)) = self.moves.get_key_value(&axis.strategy_state(&normalized)) {
+            return Some((state, mov));
+        }
+        let flipped = axis.flipped(&normalized);
+        let (state, mov) = self.moves.get_key_value(&axis.strategy_state(&flipped))?;
+        Some((state, mov.mirrored(axis.events_len())?))
+    }
+
+    /// Spoiler's move in the position, for the state as it is written:
+    /// its colours may be named in any order and it may be the mirror image of the one in the strategy.
+    pub fn lookup(&self, state: &StrategyState) -> Option<StrategyMove> {
+        self.move_on(&mut ClicquedLinearAxis::from_strategy_state(
+            state.clone(),
+            self.max_clicque,
+        ))
+    }
+
+    /// Whether following the strategy from the board wins against every answer of the Algorithm.
+    /// Only the moves of the plain game are checked, others count as losing.
+    pub fn wins_from(&self, axis: &mut ClicquedLinearAxis) -> bool {
+        self.verify(axis) == Some(true)
+    }
+
+    /// As `wins_from`, but `None` if the strategy does not win and plays a move that is not checked on the way.
+    pub fn verify(&self, axis: &mut ClicquedLinearAxis) -> Option<bool> {
+        let mut unchecked = false;
+        let wins = self.wins(axis, &mut HashMap::default(), &mut unchecked);
+        (wins || !unchecked).then_some(wins)
+    }
+
+    /// `verdicts` holds `None` for the positions being checked, met again they count as lost.
+    /// `unchecked` is set on meeting a move of another game.
+    fn wins<'a>(
+        &'a self,
+        axis: &mut ClicquedLinearAxis,
+        verdicts: &mut HashMap<&'a StrategyState, Option<bool>, GlobalRandomState>,
+        unchecked: &mut bool,
+    ) -> bool {
+        if axis.colours_used() >= self.force_num_colours {
+            return true;
+        }
+        let Some((state, mov)) = self.find(axis) else {
+            return false;
+        };
+        if let Some(&verdict) = verdicts.get(state) {
+            return verdict.unwrap_or(false);
+        }
+        verdicts.insert(state, None);
+        let wins = match mov {
+            StrategyMove::Insert { start, end } => {
+                axis.valid_new_segments().contains(&(start, end))
+                    && axis.uncollisions(start, end).into_iter().all(|color| {
+                        let reverse = axis
+                            .apply_history(History::SegmentInsert {
+                                start_index: start,
+                                end_index: end,
+                                color,
+                            })
+                            .unwrap();
+                        let wins = self.wins(axis, verdicts, unchecked);
+                        axis.apply_history(reverse);
+                        wins
+                    })
+            }
+            StrategyMove::LimitFront | StrategyMove::LimitBack => {
+                match axis.apply_history(mov.history().unwrap()) {
+                    Some(reverse) => {
+                        let wins = self.wins(axis, verdicts, unchecked);
+                        axis.apply_history(reverse);
+                        wins
+                    }
+                    None => false,
+                }
+            }
+            _ => {
+                *unchecked = true;
+                false
+            }
+        };
+        verdicts.insert(state, Some(wins));
+        wins
+    }
+
+    /// Merges strategies of the same game. Where they play differently in a position, the first move
+    /// with which the merged strategy still wins from there is kept, or the first move if none does.
+    pub fn merge(strategies: &[Strategy]) -> Result<(Strategy, Vec<Conflict>), String> {
+        let Some(first) = strategies.first() else {
+            return Err("there are no strategies to merge".to_string());
+        };
+        let mut merged = Strategy {
+            max_clicque: first.max_clicque,
+            force_num_colours: first.force_num_colours,
+            moves: HashMap::default(),
+        };
+        let mut conflicts =
+            HashMap::<StrategyState, Vec<StrategyMove>, GlobalRandomState>::default();
+        for strategy in strategies {
+            if (strategy.max_clicque, strategy.force_num_colours)
+                != (merged.max_clicque, merged.force_num_colours)
+            {
+                return Err("the strategies are of different games".to_string());
+            }
+            for (state, &mov) in &strategy.moves {
+                let mut axis =
+                    ClicquedLinearAxis::from_strategy_state(state.clone(), merged.max_clicque);
+                let Some((stored, played)) = merged.find(&mut axis) else {
+                    merged.moves.insert(state.clone(), mov);
+                    continue;
+                };
+                if same_move(&mut axis, played, mov) {
+                    continue;
+                }
+                let mov = match stored == state {
+                    true => mov,
+                    false => match mov.mirrored(axis.events_len()) {
+                        Some(mov) => mov,
+                        None => {
+                            merged.moves.insert(state.clone(), mov);
+                            continue;
+                        }
+                    },
+                };
+                let moves = conflicts
+                    .entry(stored.clone())
+                    .or_insert_with(|| vec![merged.moves[stored]]);
+                if !moves.contains(&mov) {
+                    moves.push(mov);
+                }
+            }
+        }
+        let mut conflicts = conflicts.into_iter().collect::<Vec<_>>();
+        conflicts.sort_by(|a, b| a.0.cmp(&b.0));
+        let conflicts = conflicts
+            .into_iter()
+            .map(|(state, moves)| {
+                let mut verified = true;
+                let kept = moves.iter().copied().find(|&mov| {
+                    merged.moves.insert(state.clone(), mov);
+                    let verdict = merged.verify(&mut ClicquedLinearAxis::from_strategy_state(
+                        state.clone(),
+                        merged.max_clicque,
+                    ));
+                    verified &= verdict.is_some();
+                    verdict == Some(true)
+                });
+                merged.moves.insert(state.clone(), kept.unwrap_or(moves[0]));
+                Conflict {
+                    state,
+                    moves,
+                    kept,
+                    verified: verified || kept.is_some(),
+                }
+            })
+            .collect();
+        Ok((merged, conflicts))
+    }
+
+    /// Compares the strategy, on the left, with `other`, on the right. Positions are matched up to the mirror.
+    pub fn diff(&self, other: &Strategy) -> StrategyDiff {
+        let mut diff = StrategyDiff::default();
+        for (state, &mov) in &self.moves {
+            let mut axis = ClicquedLinearAxis::from_strategy_state(state.clone(), self.max_clicque);
+            match other.move_on(&mut axis) {
+                None => diff.only_left.push(state.clone()),
+                Some(theirs) if !same_move(&mut axis, mov, theirs) => {
+                    diff.different.push((state.clone(), mov, theirs))
+                }
+                _ => {}
+            }
+        }
+        for state in other.moves.keys() {
+            let mut axis = ClicquedLinearAxis::from_strategy_state(state.clone(), self.max_clicque);
+            if self.find(&mut axis).is_none() {
+                diff.only_right.push(state.clone());
+            }
+        }
+        diff.only_left.sort();
+        diff.only_right.sort();
+        diff.different.sort_by(|a, b| a.0.cmp(&b.0));
+        diff.divergence = self.divergence(other);
+        diff
+    }
+
+    /// The first position met breadth first from the empty board where the strategies play differently.
+    fn divergence(&self, other: &Strategy) -> Option<Divergence> {
+        let mut queue = VecDeque::from([(ClicquedLinearAxis::new(self.max_clicque), vec![])]);
+        let mut seen = HashSet::<_, GlobalRandomState>::default();
+        while let Some((mut axis, line)) = queue.pop_front() {
+            if axis.colours_used() >= self.force_num_colours {
+                continue;
+            }
+            let key = axis.key();
+            if seen.contains(&key) || seen.contains(&axis.flipped_key(&key)) {
+                continue;
+            }
+            seen.insert(key);
+            let state = axis.strategy_state(&key.state());
+            let (left, right) = (self.move_on(&mut axis), other.move_on(&mut axis));
+            let same = match (left, right) {
+                (Some(left), Some(right)) => same_move(&mut axis, left, right),
+                _ => left == right,
+            };
+            if !same {
+                return Some(Divergence {
+                    line,
+                    state,
+                    left,
+                    right,
+                });
+            }
+            let Some(mov) = left else {
+                continue;
+            };
+            let mut line = line;
+            line.push((state, mov));
+            let answers = match mov {
+                StrategyMove::Insert { start, end } => axis
+                    .uncollisions(start, end)
+                    .into_iter()
+                    .map(|color| History::SegmentInsert {
+                        start_index: start,
+                        end_index: end,
+                        color,
+                    })
+                    .collect(),
+                mov => mov.history().into_iter().collect::<Vec<_>>(),
+            };
+            for answer in answers {
+                let mut next = axis.clone();
+                if next.apply_history(answer).is_some() {
+                    queue.push_back((next, line.clone()));
+                }
+            }
+        }
+        None
     }
 }
 
+/// Whether the moves are the same, or mirror images in a position symmetric to its mirror image.
+fn same_move(axis: &mut ClicquedLinearAxis, a: StrategyMove, b: StrategyMove) -> bool {
+    if a == b {
+        return true;
+    }
+    let key = axis.key();
+    axis.flipped_key(&key) == key && a.mirrored(axis.events_len()) == Some(b)
+}
+
+/// A position merged strategies play differently in, see `Strategy::merge`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub state: StrategyState,
+    pub moves: Vec<StrategyMove>,
+    /// The first of the moves with which the merged strategy wins, if any.
+    pub kept: Option<StrategyMove>,
+    /// False if none is kept and some could not be checked, see `Strategy::verify`.
+    pub verified: bool,
+}
+
+/// How two strategies differ, see `Strategy::diff`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StrategyDiff {
+    pub only_left: Vec<StrategyState>,
+    pub only_right: Vec<StrategyState>,
+    /// Positions both strategies play in, with the left move and the right one.
+    pub different: Vec<(StrategyState, StrategyMove, StrategyMove)>,
+    pub divergence: Option<Divergence>,
+}
+
+/// The first position on the way from the empty board where two strategies part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The positions leading there, each with the move both strategies play.
+    pub line: Vec<(StrategyState, StrategyMove)>,
+    pub state: StrategyState,
+    pub left: Option<StrategyMove>,
+    pub right: Option<StrategyMove>,
+}
+
 #[derive()]
 pub struct StrategyConsumer {
     moves: HashMap<StrategyState, StrategyMove, GlobalRandomState>,
@@ -124,17 +617,10 @@ impl StrategyConsumer {
         }
     }
     pub fn consume(&mut self, state: &NormalizedState, mov: StrategyMove) {
-        let state = StrategyState::from(state, self.max_colors);
-        if state.to_string() == "A[BCabAcDaC]dc" {
-            println!("{mov:?}")
-        }
-        writeln!(
-            self.wt.borrow_mut(),
-            "{} {}",
-            state.to_string(),
-            mov.string(state.front.len())
-        )
-        .unwrap();
+        self.consume_state(StrategyState::from(state, self.max_colors), mov)
+    }
+    pub fn consume_state(&mut self, state: StrategyState, mov: StrategyMove) {
+        writeln!(self.wt.borrow_mut(), "{}", state.strategy_line(mov)).unwrap();
         self.moves.insert(state, mov);
     }
 }
@@ -148,9 +634,9 @@ impl LinearAxis {
             panic!()
         };
         Self {
-            events: e.chars().map(Event::from_char).collect(),
-            front: f.chars().map(Event::from_char).collect(),
-            back: b.chars().map(Event::from_char).collect(),
+            events: events_from_string(e).into(),
+            front: events_from_string(f).into(),
+            back: events_from_string(b).into(),
         }
     }
     pub fn from_strategy_state(state: StrategyState) -> Self {
@@ -200,3 +686,265 @@ impl ClicquedLinearAxis {
 // Answer for ( [ABaCbB]cb, 0-1, C ): [ABaCbAcC]ac not found in the strategy.
 
 // Answer for ( AB[CabBcACbac] <  ): AB[CabBcA]ba not found in the strategy.
+
+#[test]
+fn test_strategy_lines() {
+    let state = StrategyState::from_string("A[BCabAcDaC]dc").unwrap();
+    assert_eq!(state.to_string(), "A[BCabAcDaC]dc");
+    for mov in [
+        StrategyMove::Insert { start: 2, end: 6 },
+        StrategyMove::InsertWithList {
+            start: 0,
+            end: 3,
+            list: 0b1010,
+        },
+        StrategyMove::InsertWithList {
+            start: 0,
+            end: 3,
+            list: 1 << 21 | 1,
+        },
+        StrategyMove::InsertWithDemand {
+            start: 1,
+            end: 4,
+            demand: 2,
+        },
+        StrategyMove::Remove { start: 3, end: 5 },
+        StrategyMove::LimitFront,
+        StrategyMove::LimitBack,
+    ] {
+        assert_eq!(
+            StrategyState::from_strategy_line(&state.strategy_line(mov)),
+            Some((state.clone(), mov))
+        );
+    }
+    assert_eq!(StrategyState::from_strategy_line("[Aa] 2"), None);
+    // Letters late in the alphabet are colours of large clicques.
+    let state = StrategyState::from_string("[AUau]").unwrap();
+    assert_eq!(state.actual[1], Event::new_start(20));
+
+    let file = "3 5\n[] 0 1\n[Aa]b >\n";
+    let strategy = Strategy::read(&mut file.as_bytes()).unwrap();
+    assert_eq!((strategy.max_clicque, strategy.force_num_colours), (3, 5));
+    assert_eq!(
+        strategy.moves[&StrategyState::from_string("[Aa]b").unwrap()],
+        StrategyMove::LimitFront
+    );
+    assert!(Strategy::read(&mut "[] 0 1\n".as_bytes()).is_err());
+}
+
+#[cfg(test)]
+fn solved_strategy() -> Strategy {
+    use std::{fs::File, io::BufReader};
+
+    let path = std::env::temp_dir().join(format!(
+        "solved-{}-{:?}.strategy",
+        std::process::id(),
+        std::thread::current().id()
+    ));
+    let consumer = StrategyConsumer::new(10, 3, 5, Box::new(File::create(&path).unwrap()));
+    let mut game = super::game::Game::new(9, 3, 5, Some(consumer));
+    assert!(game.simulate(-1));
+    drop(game);
+    let strategy = Strategy::read(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+    std::fs::remove_file(path).unwrap();
+    strategy
+}
+
+/// The strategy with every position seen in a mirror.
+#[cfg(test)]
+fn mirrored_strategy(strategy: &Strategy) -> Strategy {
+    let mut mirrored = Strategy {
+        moves: HashMap::default(),
+        ..*strategy
+    };
+    for (state, &mov) in &strategy.moves {
+        let mut axis = ClicquedLinearAxis::from_strategy_state(state.clone(), strategy.max_clicque);
+        let key = axis.key();
+        let flipped = axis.flipped_key(&key);
+        mirrored.moves.insert(
+            axis.strategy_state(&flipped.state()),
+            mov.mirrored(axis.events_len()).unwrap(),
+        );
+    }
+    mirrored
+}
+
+/// The strategy with one move swapped for another insertion that loses, and the position it was in.
+#[cfg(test)]
+fn spoiled_strategy(strategy: &Strategy) -> (Strategy, StrategyState) {
+    let mut states = strategy.moves.keys().cloned().collect::<Vec<_>>();
+    states.sort();
+    for state in states {
+        let mut axis = ClicquedLinearAxis::from_strategy_state(state.clone(), strategy.max_clicque);
+        for (start, end) in axis.valid_new_segments() {
+            let mut spoiled = strategy.clone();
+            spoiled
+                .moves
+                .insert(state.clone(), StrategyMove::Insert { start, end });
+            if !spoiled.wins_from(&mut axis) {
+                return (spoiled, state);
+            }
+        }
+    }
+    unreachable!()
+}
+
+#[test]
+fn test_strategy_wins() {
+    super::game::with_search_stack(|| {
+        let strategy = solved_strategy();
+        let mut root = ClicquedLinearAxis::new(3);
+        assert!(strategy.wins_from(&mut root));
+        assert!(mirrored_strategy(&strategy).wins_from(&mut root));
+
+        let mut written = vec![];
+        strategy.write(&mut written).unwrap();
+        let read = Strategy::read(&mut written.as_slice()).unwrap();
+        assert_eq!(read.moves, strategy.moves);
+
+        let (spoiled, _) = spoiled_strategy(&strategy);
+        assert!(!spoiled.wins_from(&mut root));
+        let mut stuck = strategy.clone();
+        let empty = StrategyState::from_string("[]").unwrap();
+        stuck.moves.insert(empty, StrategyMove::LimitFront);
+        assert!(!stuck.wins_from(&mut root));
+    });
+}
+
+#[test]
+fn test_merge_strategies() {
+    super::game::with_search_stack(|| {
+        let strategy = solved_strategy();
+        let (merged, conflicts) =
+            Strategy::merge(&[strategy.clone(), mirrored_strategy(&strategy)]).unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.moves, strategy.moves);
+
+        let (spoiled, state) = spoiled_strategy(&strategy);
+        let (merged, conflicts) = Strategy::merge(&[spoiled.clone(), strategy.clone()]).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].state, state);
+        assert_eq!(conflicts[0].moves[0], spoiled.moves[&state]);
+        assert_eq!(conflicts[0].kept, Some(strategy.moves[&state]));
+        assert!(conflicts[0].verified);
+        assert!(merged.wins_from(&mut ClicquedLinearAxis::new(3)));
+    });
+}
+
+#[test]
+fn test_tied_and_unchecked_moves() {
+    let tied = StrategyMove::InsertWithDemand {
+        start: 0,
+        end: 1,
+        demand: 2,
+    };
+    assert_eq!(tied.mirrored(2), None);
+    let read = |file: &str| Strategy::read(&mut file.as_bytes()).unwrap();
+    // `[ABabCc]` is the mirror image of `[AaBCbc]`.
+    let (state, flipped) = (
+        StrategyState::from_string("[AaBCbc]").unwrap(),
+        StrategyState::from_string("[ABabCc]").unwrap(),
+    );
+    let with_demand = read("3 5\n[AaBCbc] 0 1 2\n");
+    assert_eq!(with_demand.lookup(&state), Some(tied));
+    assert_eq!(with_demand.lookup(&flipped), None);
+    let plain = read("3 5\n[ABabCc] 0 1\n");
+    // The plain move can be mirrored onto the position, the tied one cannot.
+    let diff = with_demand.diff(&plain);
+    assert_eq!(diff.different.len(), 1);
+    assert_eq!(diff.only_right, vec![flipped]);
+    for strategies in [[&with_demand, &plain], [&plain, &with_demand]] {
+        let strategies = strategies.map(Strategy::clone);
+        let (merged, conflicts) = Strategy::merge(&strategies).unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.moves.len(), 2);
+    }
+
+    // Moves of other games cannot be checked, so neither can a conflict with one.
+    let root = StrategyState::from_string("[]").unwrap();
+    let with_demand = read("3 5\n[] 0 0 2\n");
+    assert_eq!(with_demand.verify(&mut ClicquedLinearAxis::new(3)), None);
+    assert!(!with_demand.wins_from(&mut ClicquedLinearAxis::new(3)));
+    let plain = read("3 5\n[] 0 0\n");
+    assert_eq!(plain.verify(&mut ClicquedLinearAxis::new(3)), Some(false));
+    for strategies in [[&with_demand, &plain], [&plain, &with_demand]] {
+        let strategies = strategies.map(Strategy::clone);
+        let (_, conflicts) = Strategy::merge(&strategies).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].state, root);
+        assert_eq!(conflicts[0].kept, None);
+        assert!(!conflicts[0].verified);
+    }
+
+    assert!(Strategy::merge(&[]).is_err());
+    assert!(Strategy::merge(&[plain, read("3 6\n")]).is_err());
+}
+
+#[test]
+fn test_diff_strategies() {
+    super::game::with_search_stack(|| {
+        let strategy = solved_strategy();
+        assert_eq!(strategy.diff(&strategy), StrategyDiff::default());
+        assert_eq!(
+            strategy.diff(&mirrored_strategy(&strategy)),
+            StrategyDiff::default()
+        );
+
+        let (mut spoiled, state) = spoiled_strategy(&strategy);
+        let empty = StrategyState::from_string("[]").unwrap();
+        let root_move = spoiled.moves.remove(&empty).unwrap();
+        let diff = strategy.diff(&spoiled);
+        assert_eq!(diff.only_left, vec![empty.clone()]);
+        assert!(diff.only_right.is_empty());
+        assert_eq!(
+            diff.different,
+            vec![(state.clone(), strategy.moves[&state], spoiled.moves[&state])]
+        );
+        let divergence = diff.divergence.unwrap();
+        assert!(divergence.line.is_empty());
+        assert_eq!(
+            (divergence.state, divergence.left, divergence.right),
+            (empty.clone(), Some(root_move), None)
+        );
+
+        spoiled.moves.insert(empty, root_move);
+        let divergence = strategy.diff(&spoiled).divergence.unwrap();
+        assert!(!divergence.line.is_empty());
+        assert_ne!(divergence.left, divergence.right);
+        let mut axis = ClicquedLinearAxis::from_strategy_state(divergence.state, 3);
+        assert_eq!(spoiled.move_on(&mut axis), divergence.right);
+    });
+}
+
+#[test]
+fn test_lookup() {
+    super::game::with_search_stack(|| {
+        let strategy = solved_strategy();
+        let mirrored = mirrored_strategy(&strategy);
+        // Colours named the other way round.
+        let renamed = |state: &StrategyState| {
+            let swapped = state
+                .to_string()
+                .chars()
+                .map(|c| match c {
+                    'A' => 'B',
+                    'B' => 'A',
+                    'a' => 'b',
+                    'b' => 'a',
+                    c => c,
+                })
+                .collect::<String>();
+            StrategyState::from_string(&swapped).unwrap()
+        };
+        for (state, &mov) in &strategy.moves {
+            assert_eq!(strategy.lookup(state), Some(mov));
+            assert_eq!(strategy.lookup(&renamed(state)), Some(mov));
+            let mut axis = ClicquedLinearAxis::from_strategy_state(state.clone(), 3);
+            assert!(same_move(&mut axis, mirrored.lookup(state).unwrap(), mov));
+        }
+        let mut partial = strategy.clone();
+        let empty = StrategyState::from_string("[]").unwrap();
+        partial.moves.remove(&empty);
+        assert_eq!(partial.lookup(&empty), None);
+    });
+}
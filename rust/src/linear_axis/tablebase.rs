@@ -0,0 +1,328 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use crate::hash::GlobalRandomState;
+
+use super::{
+    clicqued::ClicquedLinearAxis,
+    game::{Game, GameAxis},
+    normalization::{decompress_events, CompressedState, NormalizedState},
+    strategy::{StrategyConsumer, StrategyMove},
+};
+
+/// What the search concluded about a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Spoiler wins with `mov` in at most `distance` moves.
+    Win {
+        distance: u8,
+        mov: Option<StrategyMove>,
+    },
+    /// The most colours Spoiler can force.
+    Loss { colours: u8 },
+}
+
+/// Every position of the plain linear game up to `max_events` events, solved once and for all.
+/// Seeding a `Game` with it makes deeper searches stop as soon as they reach a known win.
+#[derive(Debug, Clone)]
+pub struct Tablebase {
+    pub max_clicque: usize,
+    pub force_num_colours: usize,
+    pub max_events: usize,
+    pub entries: HashMap<CompressedState, Verdict, GlobalRandomState>,
+}
+
+/// A search stopped before it finished: the position it started from and the wins it found.
+/// Losses depend on the order the search went in, so they are not kept and have to be searched again.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub root: NormalizedState,
+    pub tablebase: Tablebase,
+}
+
+impl Tablebase {
+    pub fn build(max_clicque: usize, force_num_colours: usize, max_events: usize) -> Self {
+        let mut game = Game::new(max_events, max_clicque, force_num_colours, None);
+        let mut axis = ClicquedLinearAxis::new(max_clicque);
+        for segments in 0..=max_events / 2 {
+            for state in axis.generate_all_states(segments as isize) {
+                game.solve_from(&state);
+            }
+        }
+        Self::from_game(&mut game)
+    }
+
+    /// Every position the game has settled so far.
+    pub fn from_game(game: &mut Game) -> Self {
+        let axis = ClicquedLinearAxis::new(game.max_clicque());
+        let entries = game
+            .verdicts()
+            .into_iter()
+            .map(|(state, verdict)| (axis.with_normalized(&state).normalize_compress(), verdict))
+            .collect();
+        Self {
+            max_clicque: game.max_clicque(),
+            force_num_colours: game.force_num_colours(),
+            max_events: game.max_events(),
+            entries,
+        }
+    }
+
+    /// Seeds the game with the wins, which hold for any number of colours up to `force_num_colours`
+    /// and at least `max_events` events, and returns how many were used. Losses are left out: a position met again while still being searched
+    /// counts as lost, so they depend on the order the search went in.
+    pub fn seed(&self, game: &mut Game) -> usize {
+        assert_eq!(game.max_clicque(), self.max_clicque);
+        if game.force_num_colours() > self.force_num_colours || game.max_events() < self.max_events
+        {
+            return 0;
+        }
+        let mut seeded = 0;
+        for (key, &verdict) in &self.entries {
+            if let Verdict::Win { .. } = verdict {
+                game.seed(&NormalizedState(decompress_events(key)), verdict);
+                seeded += 1;
+            }
+        }
+        seeded
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&[
+            self.max_clicque as u8,
+            self.force_num_colours as u8,
+            self.max_events as u8,
+        ])?;
+        w.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for (key, verdict) in &self.entries {
+            w.write_all(&[key.len() as u8])?;
+            w.write_all(key)?;
+            match *verdict {
+                Verdict::Loss { colours } => w.write_all(&[0, colours])?,
+                Verdict::Win { distance, mov } => {
+                    w.write_all(&[1, distance])?;
+                    w.write_all(&encode_move(mov))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read(r: &mut impl Read) -> io::Result<Self> {
+        let mut header = [0u8; 3];
+        r.read_exact(&mut header)?;
+        let mut len = [0u8; 4];
+        r.read_exact(&mut len)?;
+        let mut entries = HashMap::default();
+        for _ in 0..u32::from_le_bytes(len) {
+            let mut key_len = [0u8];
+            r.read_exact(&mut key_len)?;
+            let mut key = vec![0u8; key_len[0] as usize];
+            r.read_exact(&mut key)?;
+            let mut verdict = [0u8; 2];
+            r.read_exact(&mut verdict)?;
+            let verdict = match verdict {
+                [0, colours] => Verdict::Loss { colours },
+                [1, distance] => {
+                    let mut mov = [0u8; 3];
+                    r.read_exact(&mut mov)?;
+                    Verdict::Win {
+                        distance,
+                        mov: decode_move(mov)?,
+                    }
+                }
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "bad verdict")),
+            };
+            entries.insert(key, verdict);
+        }
+        Ok(Self {
+            max_clicque: header[0] as usize,
+            force_num_colours: header[1] as usize,
+            max_events: header[2] as usize,
+            entries,
+        })
+    }
+}
+
+impl Checkpoint {
+    pub fn from_game(game: &mut Game) -> Self {
+        let mut tablebase = Tablebase::from_game(game);
+        tablebase
+            .entries
+            .retain(|_, verdict| matches!(verdict, Verdict::Win { .. }));
+        Self {
+            root: game.position(),
+            tablebase,
+        }
+    }
+
+    /// A game at the root seeded with the checkpoint, with at least as many events.
+    pub fn resume(&self, max_events: usize, strategy: Option<StrategyConsumer>) -> Game {
+        let axis = ClicquedLinearAxis::new(self.tablebase.max_clicque).with_normalized(&self.root);
+        let mut game =
+            Game::with_axis(axis, max_events, self.tablebase.force_num_colours, strategy);
+        self.tablebase.seed(&mut game);
+        game
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        let root = ClicquedLinearAxis::new(self.tablebase.max_clicque)
+            .with_normalized(&self.root)
+            .normalize_compress();
+        w.write_all(&[root.len() as u8])?;
+        w.write_all(&root)?;
+        self.tablebase.write(w)
+    }
+
+    pub fn read(r: &mut impl Read) -> io::Result<Self> {
+        let mut root_len = [0u8];
+        r.read_exact(&mut root_len)?;
+        let mut root = vec![0u8; root_len[0] as usize];
+        r.read_exact(&mut root)?;
+        Ok(Self {
+            root: NormalizedState(decompress_events(&root)),
+            tablebase: Tablebase::read(r)?,
+        })
+    }
+}
+
+fn encode_move(mov: Option<StrategyMove>) -> [u8; 3] {
+    match mov {
+        None => [0, 0, 0],
+        Some(StrategyMove::LimitFront) => [1, 0, 0],
+        Some(StrategyMove::LimitBack) => [2, 0, 0],
+        Some(StrategyMove::Insert { start, end }) => [3, start as u8, end as u8],
+        Some(mov) => panic!("Not a move of the plain game: {mov:?}"),
+    }
+}
+
+fn decode_move(mov: [u8; 3]) -> io::Result<Option<StrategyMove>> {
+    Ok(match mov {
+        [0, _, _] => None,
+        [1, _, _] => Some(StrategyMove::LimitFront),
+        [2, _, _] => Some(StrategyMove::LimitBack),
+        [3, start, end] => Some(StrategyMove::Insert {
+            start: start as usize,
+            end: end as usize,
+        }),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "bad move")),
+    })
+}
+
+#[test]
+fn test_tablebase() {
+    super::game::with_search_stack(|| {
+        use super::strategy::StrategyConsumer;
+
+        let tablebase = Tablebase::build(3, 5, 9);
+        let mut file = vec![];
+        tablebase.write(&mut file).unwrap();
+        let loaded = Tablebase::read(&mut file.as_slice()).unwrap();
+        assert_eq!(loaded.entries, tablebase.entries);
+        assert_eq!(loaded.max_events, 9);
+
+        let axis = ClicquedLinearAxis::new(3);
+        for key in loaded.entries.keys() {
+            let state = NormalizedState(decompress_events(key));
+            assert_eq!(&axis.with_normalized(&state).normalize_compress(), key);
+        }
+
+        // The empty board is already won in the table, the strategy is read straight from it.
+        let strategy = StrategyConsumer::new(10, 3, 5, Box::new(std::io::sink()));
+        let mut game = Game::new(13, 3, 5, Some(strategy));
+        let seeded = loaded.seed(&mut game);
+        assert!(game.simulate(-1));
+        assert_eq!(game.number_of_states(), seeded);
+    });
+}
+
+#[test]
+fn test_seeding_wins() {
+    super::game::with_search_stack(|| {
+        use super::ordering::MoveOrdering;
+        use clap::ValueEnum;
+
+        // Both games are won, though without help some orderings make up a loss on 7 events (see `test_move_ordering`).
+        // Seeding them with losses found in yet another order loses the game ordered by fewest responses.
+        let tablebase = Tablebase::build(3, 5, 7);
+        for max_events in [7, 9] {
+            for &ordering in MoveOrdering::value_variants() {
+                let mut seeded = Game::new(max_events, 3, 5, None);
+                seeded.move_ordering = ordering;
+                tablebase.seed(&mut seeded);
+                assert!(seeded.simulate(-1), "{max_events} {ordering:?}");
+            }
+        }
+    });
+}
+
+#[test]
+fn test_checkpoint() {
+    super::game::with_search_stack(|| {
+        use crate::limits::SearchOutcome;
+
+        let mut game = Game::new(9, 3, 5, None);
+        game.limits.nodes = Some(100);
+        assert_eq!(game.solve(-1), SearchOutcome::Unknown);
+        let checkpoint = Checkpoint::from_game(&mut game);
+        assert!(!checkpoint.tablebase.entries.is_empty());
+        let mut file = vec![];
+        checkpoint.write(&mut file).unwrap();
+        assert_eq!(
+            Checkpoint::read(&mut file.as_slice())
+                .unwrap()
+                .resume(9, None)
+                .solve(-1),
+            SearchOutcome::Win
+        );
+
+        // A finished search has losses too, the checkpoint leaves them out.
+        let mut game = Game::new(7, 3, 5, None);
+        assert_eq!(game.solve(-1), SearchOutcome::Loss);
+        let checkpoint = Checkpoint::from_game(&mut game);
+        assert!(checkpoint.tablebase.entries.len() < game.number_of_states());
+        assert!(checkpoint
+            .tablebase
+            .entries
+            .values()
+            .all(|verdict| matches!(verdict, Verdict::Win { .. })));
+    });
+}
+
+#[test]
+fn test_resume_checkpoint() {
+    super::game::with_search_stack(|| {
+        use super::History;
+        use crate::limits::SearchOutcome;
+        use std::sync::{atomic::AtomicBool, Arc};
+
+        let mut axis = ClicquedLinearAxis::new(3);
+        axis.apply_history(History::SegmentInsert {
+            start_index: 0,
+            end_index: 0,
+            color: 0,
+        })
+        .unwrap();
+        let mut game = Game::with_axis(axis, 9, 5, None);
+        let root = game.position();
+        game.limits.nodes = Some(100);
+        assert_eq!(game.solve(-1), SearchOutcome::Unknown);
+        // Cancelled searches unwind back to the root.
+        game.limits.nodes = None;
+        game.limits.cancel = Some(Arc::new(AtomicBool::new(true)));
+        assert_eq!(game.solve(-1), SearchOutcome::Unknown);
+        assert_eq!(game.position(), root);
+
+        let mut file = vec![];
+        Checkpoint::from_game(&mut game).write(&mut file).unwrap();
+        let checkpoint = Checkpoint::read(&mut file.as_slice()).unwrap();
+        assert_eq!(checkpoint.root, root);
+        for max_events in [9, 10] {
+            let mut resumed = checkpoint.resume(max_events, None);
+            assert_eq!(resumed.position(), root);
+            assert_eq!(resumed.solve(-1), SearchOutcome::Win);
+        }
+    });
+}
@@ -1,10 +1,23 @@
 use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use clap::*;
-use segment_colouring::linear_axis::game::Game;
+use segment_colouring::limits::{SearchLimits, SearchOutcome};
+use segment_colouring::linear_axis::circular::CircularAxis;
+use segment_colouring::linear_axis::game::{parse_max_events, Game, GameAxis, MAX_EVENTS};
+use segment_colouring::linear_axis::ordering::MoveOrdering;
 use segment_colouring::linear_axis::strategy::StrategyConsumer;
+use segment_colouring::linear_axis::tablebase::{Checkpoint, Tablebase};
+use segment_colouring::presentation::Presentation;
+use segment_colouring::progress::{ProgressFormat, ProgressReporter};
+
+/// Where an interrupted search is saved when no `--checkpoint` is given.
+const DEFAULT_CHECKPOINT: &str = "./rust.checkpoint";
 
 /// This is to prevent stack overflow.
 /// We currently clone the state of the game to be restored when another simulation branch is evaluated.
@@ -25,26 +38,216 @@ struct Args {
     max_clicque: usize,
     /// The simulation will reduce the game when the <MAX_EVENTS> number of events is reached.
     /// The higher this number is the longer it takes to simulate but the chance of success is higher
+    #[arg(value_parser = parse_max_events)]
     max_events: usize,
+    /// Play on a circle (proper circular-arc colouring) instead of a line.
+    /// There are no barriers on a circle, so the board simply stops growing at <MAX_EVENTS>.
+    #[arg(long)]
+    circular: bool,
+    /// After each insertion the colouring algorithm may recolour up to this many segments.
+    #[arg(long, default_value_t = 0)]
+    recourse: usize,
+    /// Spoiler has to present this many segments in advance, before the current one gets coloured.
+    #[arg(long, default_value_t = 0)]
+    lookahead: usize,
+    /// Spoiler hands out a list of this many colours with every segment and the colouring algorithm
+    /// has to pick one of them (online list colouring).
+    #[arg(long, default_value_t = 0, conflicts_with = "lookahead")]
+    list_size: usize,
+    /// Spoiler may ask for up to this many distinct colours for a single segment (online multicolouring).
+    /// The clicque size is then counted in total demand.
+    #[arg(long, default_value_t = 1, conflicts_with_all = ["lookahead", "list_size", "circular"])]
+    max_demand: usize,
+    /// Spoiler shrinks the board by deleting segments of its choice instead of moving barriers
+    /// (fully dynamic colouring, the clicque bound applies to the segments present).
+    #[arg(long)]
+    deletions: bool,
+    /// The order Spoiler has to present the segments in.
+    #[arg(long, value_enum, default_value_t = Presentation::Any, conflicts_with_all = ["circular", "deletions"])]
+    presentation: Presentation,
+    /// Before expanding a state, look for a won one reachable by moving the barriers several times.
+    #[arg(long, conflicts_with_all = ["circular", "deletions"])]
+    barrier_lookahead: bool,
+    /// Seed the search with the positions solved in this tablebase (see the `tablebase` binary).
+    #[arg(long, conflicts_with_all = ["circular", "recourse", "lookahead", "list_size", "max_demand", "deletions", "presentation"])]
+    tablebase: Option<String>,
+    /// The order Spoiler's moves are tried in.
+    #[arg(long, value_enum, default_value_t = MoveOrdering::Index)]
+    move_ordering: MoveOrdering,
+    /// Before the full search, look for a good line with a beam of this width.
+    #[arg(long, conflicts_with = "circular")]
+    beam: Option<usize>,
+    /// How many moves deep the beam search values positions.
+    #[arg(long, default_value_t = 1, requires = "beam")]
+    beam_depth: isize,
+    /// Give up after this many seconds.
+    #[arg(long)]
+    time_limit: Option<f64>,
+    /// Give up after expanding this many positions.
+    #[arg(long)]
+    node_limit: Option<usize>,
+    /// Give up once the table of positions takes about this many bytes.
+    #[arg(long)]
+    table_limit: Option<usize>,
+    /// Keep the lost positions in the table within about this many bytes, evicting those cheapest to search again.
+    /// Won positions are always kept.
+    #[arg(long)]
+    table_budget: Option<usize>,
+    /// Keep the table of positions in sorted runs in this directory, for searches that outgrow memory.
+    #[arg(long, conflicts_with = "table_budget")]
+    disk_store: Option<String>,
+    /// How many bytes of positions `--disk-store` keeps in memory before writing them out.
+    #[arg(long, default_value_t = 1 << 30)]
+    disk_memory: usize,
+    /// Save the positions won by the search to this file, to resume it with `--resume`.
+    /// Searches that hit a limit or get interrupted with Ctrl-C are always saved, by default to ./rust.checkpoint.
+    #[arg(long, conflicts_with_all = ["circular", "recourse", "lookahead", "list_size", "max_demand", "deletions", "presentation"])]
+    checkpoint: Option<String>,
+    /// Resume a search from a checkpoint, with the same or a larger <MAX_EVENTS>.
+    #[arg(long, conflicts_with_all = ["circular", "recourse", "lookahead", "list_size", "max_demand", "deletions", "presentation"])]
+    resume: Option<String>,
+    /// Report on the search to stderr while it runs.
+    #[arg(long, value_enum)]
+    progress: Option<ProgressFormat>,
+    /// Seconds between progress reports.
+    #[arg(long, default_value_t = 1.0, requires = "progress")]
+    progress_interval: f64,
 }
 
-fn run(args: Args) {
+fn run(args: Args, cancel: Arc<AtomicBool>) {
     let strategy = StrategyConsumer::new(
         args.desired_number_of_colours * 2,
         args.max_clicque as usize,
         args.desired_number_of_colours,
         Box::new(File::create("./rust.strategy").unwrap()),
     );
-    let mut game = Game::new(
-        args.max_events,
-        args.max_clicque,
-        args.desired_number_of_colours,
-        Some(strategy),
-    );
+    if args.circular {
+        let game = Game::with_axis(
+            CircularAxis::new(args.max_clicque),
+            args.max_events,
+            args.desired_number_of_colours,
+            Some(strategy),
+        );
+        simulate(game, &args, cancel);
+    } else {
+        let mut game = match &args.resume {
+            Some(path) => {
+                let checkpoint =
+                    Checkpoint::read(&mut BufReader::new(File::open(path).unwrap())).unwrap();
+                assert_eq!(
+                    (
+                        checkpoint.tablebase.max_clicque,
+                        checkpoint.tablebase.force_num_colours
+                    ),
+                    (args.max_clicque, args.desired_number_of_colours),
+                    "The checkpoint is of a game with other parameters."
+                );
+                assert!(
+                    checkpoint.tablebase.max_events <= args.max_events,
+                    "The checkpoint is of a game with more events."
+                );
+                println!(
+                    "Resuming with {} won states.",
+                    checkpoint.tablebase.entries.len()
+                );
+                checkpoint.resume(args.max_events, Some(strategy))
+            }
+            None => Game::new(
+                args.max_events,
+                args.max_clicque,
+                args.desired_number_of_colours,
+                Some(strategy),
+            ),
+        };
+        if let Some(width) = args.beam {
+            let mut beam_game = Game::new(
+                args.max_events,
+                args.max_clicque,
+                args.desired_number_of_colours,
+                None,
+            );
+            configure(&mut beam_game, &args);
+            // The limits are for the full search.
+            beam_game.limits = SearchLimits::default();
+            let start = Instant::now();
+            let line = beam_game.beam_search(width, args.beam_depth, 4 * args.max_events);
+            println!(
+                "The beam search found a line reaching {} colours in {:?}:",
+                line.colours,
+                start.elapsed()
+            );
+            for (state, mov) in &line.steps {
+                println!("  {}", state.strategy_line(*mov));
+            }
+        }
+        if let Some(path) = &args.tablebase {
+            let tablebase =
+                Tablebase::read(&mut BufReader::new(File::open(path).unwrap())).unwrap();
+            println!(
+                "Seeded {} states from the tablebase.",
+                tablebase.seed(&mut game)
+            );
+        }
+        let (mut game, outcome) = simulate(game, &args, cancel);
+        let path = match &args.checkpoint {
+            Some(path) => Some(path.as_str()),
+            None => (outcome == SearchOutcome::Unknown).then_some(DEFAULT_CHECKPOINT),
+        };
+        if let Some(path) = path {
+            let checkpoint = Checkpoint::from_game(&mut game);
+            checkpoint
+                .write(&mut BufWriter::new(File::create(path).unwrap()))
+                .unwrap();
+            println!(
+                "Saved {} won states to {}, continue with --resume {}.",
+                checkpoint.tablebase.entries.len(),
+                path,
+                path
+            );
+        }
+    }
+}
+
+fn configure<A: GameAxis>(game: &mut Game<A>, args: &Args) {
+    game.recourse = args.recourse;
+    game.lookahead = args.lookahead;
+    game.list_size = args.list_size;
+    game.max_demand = args.max_demand;
+    game.deletions = args.deletions;
+    game.presentation = args.presentation;
+    game.barrier_lookahead = args.barrier_lookahead;
+    game.move_ordering = args.move_ordering;
+    game.limits.time = args.time_limit.map(Duration::from_secs_f64);
+    game.limits.nodes = args.node_limit;
+    game.limits.table_bytes = args.table_limit;
+    if let Some(bytes) = args.table_budget {
+        game.bound_table(bytes);
+    }
+    if let Some(dir) = &args.disk_store {
+        game.store_on_disk(dir, args.disk_memory).unwrap();
+    }
+    game.progress = args.progress.map(|format| {
+        ProgressReporter::new(format, Duration::from_secs_f64(args.progress_interval))
+    });
+}
+
+fn simulate<A: GameAxis>(
+    mut game: Game<A>,
+    args: &Args,
+    cancel: Arc<AtomicBool>,
+) -> (Game<A>, SearchOutcome) {
+    configure(&mut game, args);
+    game.limits.cancel = Some(cancel);
     let start = Instant::now();
-    let result = game.simulate(-1);
+    let result = game.solve(-1);
     let elapsed = start.elapsed();
-    if result {
+    if result == SearchOutcome::Unknown {
+        println!("UNKNOWN!");
+        println!(
+            "The search was stopped before deciding whether {} colours can be forced.",
+            args.desired_number_of_colours
+        );
+    } else if result == SearchOutcome::Win {
         println!("SUCCESS!");
         println!("It IS possible to force any colouring algorithm to use {} colours whithout creating a clicque larger than {}.", args.desired_number_of_colours, args.max_clicque);
         println!(
@@ -54,24 +257,106 @@ fn run(args: Args) {
     } else {
         println!("FAILURE!");
         println!(
-            "It is NOT possible to force any colouring algorithm to use {} colours whithout creating a clicque larger than {} when the simulation is confined to states with at most {} events.", 
+            "No way was found to force any colouring algorithm to use {} colours whithout creating a clicque larger than {} when the simulation is confined to states with at most {} events.", 
             args.desired_number_of_colours,
             args.max_clicque,
             args.max_events
-        )
+        );
+        println!(
+            "Positions met again on the search path count as lost, so this depends on the {:?} move ordering and another one may still find a way.",
+            args.move_ordering
+        );
+        println!(
+            "The most colours the simulation could force was {}.",
+            game.forced_colours(-1)
+        );
+    }
+    if args.lookahead > 0 {
+        println!(
+            "The colouring algorithm could see {} segments ahead of the one it was colouring.",
+            args.lookahead
+        );
+    }
+    if args.list_size > 0 {
+        println!(
+            "Every segment came with a list of {} colours to choose from.",
+            args.list_size
+        );
+    }
+    if args.max_demand > 1 {
+        println!("A segment could demand up to {} colours.", args.max_demand);
+    }
+    if args.deletions {
+        println!("Spoiler could delete segments instead of using barriers.");
+    }
+    match args.presentation {
+        Presentation::Any => {}
+        Presentation::LeftEndpoint => {
+            println!("The segments were presented in order of their left endpoints.")
+        }
+        Presentation::RightEndpoint => {
+            println!("The segments were presented in order of their right endpoints.")
+        }
+    }
+    if args.recourse > 0 {
+        println!(
+            "The colouring algorithm was allowed to recolour up to {} segments after each insertion.",
+            args.recourse
+        );
     }
     println!(
-        "\nThe simulation discovered {} states in {:?}.",
+        "\nThe simulation discovered {} states in {:?}, expanding {} positions with {:?} move ordering.",
         game.number_of_states(),
-        elapsed
+        elapsed,
+        game.nodes(),
+        args.move_ordering
     );
+    if args.table_budget.is_some() {
+        println!(
+            "{} lost positions were evicted from the table to stay within its budget.",
+            game.evicted_states()
+        );
+    }
+    (game, result)
+}
+
+/// Stops the search at the first Ctrl-C so that it can be saved, exits at the second.
+fn handle_interrupts() -> Arc<AtomicBool> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let flag = cancel.clone();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+    thread::spawn(move || {
+        runtime.block_on(async {
+            tokio::signal::ctrl_c().await.unwrap();
+            eprintln!("Interrupted, saving the search. Press Ctrl-C again to quit right away.");
+            flag.store(true, Ordering::Relaxed);
+            tokio::signal::ctrl_c().await.unwrap();
+            std::process::exit(130);
+        })
+    });
+    cancel
 }
 
 fn main() {
     let args = Args::parse();
+    if args.max_events + 2 * (args.max_demand - 1) > MAX_EVENTS {
+        Args::command()
+            .error(
+                error::ErrorKind::ValueValidation,
+                format!(
+                    "segments demanding {} colours do not fit on boards of {} events, at most {} events are supported",
+                    args.max_demand, args.max_events, MAX_EVENTS
+                ),
+            )
+            .exit();
+    }
+    let cancel = handle_interrupts();
     let child = thread::Builder::new()
         .stack_size(STACK_SIZE)
-        .spawn(move || run(args))
+        .spawn(move || run(args, cancel))
         .unwrap();
 
     child.join().unwrap();
@@ -0,0 +1,51 @@
+/// The order Spoiler has to present the segments in.
+/// Ordered presentations break the mirror symmetry of the board,
+/// so positions are no longer identified with their mirror images.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Presentation {
+    /// Segments come in any order.
+    #[default]
+    Any,
+    /// Every segment starts to the right of the segments presented before.
+    LeftEndpoint,
+    /// Every segment ends to the right of the segments presented before.
+    RightEndpoint,
+}
+
+impl Presentation {
+    /// Whether a segment with its start at gap `start` and its end at gap `end` may come next.
+    /// Both gaps are counted before the insertion, `is_start` tells the kind of each event on the board.
+    pub fn allows(
+        &self,
+        start: usize,
+        end: usize,
+        mut is_start: impl DoubleEndedIterator<Item = bool> + ExactSizeIterator,
+    ) -> bool {
+        let mut after_last = |kind: bool| is_start.rposition(|s| s == kind).map_or(0, |i| i + 1);
+        match self {
+            Self::Any => true,
+            Self::LeftEndpoint => start >= after_last(true),
+            Self::RightEndpoint => end >= after_last(false),
+        }
+    }
+    pub fn is_symmetric(&self) -> bool {
+        *self == Self::Any
+    }
+}
+
+#[test]
+fn test_presentation() {
+    // AaBCbc
+    let board = [true, false, true, true, false, false];
+    let allowed = |p: Presentation| {
+        (0..=board.len())
+            .flat_map(|s| (s..=board.len()).map(move |e| (s, e)))
+            .filter(|&(s, e)| p.allows(s, e, board.iter().copied()))
+            .count()
+    };
+    assert_eq!(allowed(Presentation::Any), 28);
+    assert_eq!(allowed(Presentation::LeftEndpoint), 6);
+    assert_eq!(allowed(Presentation::RightEndpoint), 7);
+    assert!(Presentation::LeftEndpoint.allows(4, 6, board.iter().copied()));
+    assert!(!Presentation::LeftEndpoint.allows(3, 6, board.iter().copied()));
+}
@@ -0,0 +1,112 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// How progress lines are written to stderr.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum ProgressFormat {
+    #[default]
+    Human,
+    /// One JSON object per line.
+    Json,
+}
+
+/// A snapshot of a running search.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Progress {
+    pub seconds: f64,
+    /// Positions in the table.
+    pub states: usize,
+    /// Positions expanded.
+    pub nodes: usize,
+    pub nodes_per_second: f64,
+    /// Moves from the root to the position being expanded.
+    pub depth: usize,
+    pub most_colours: usize,
+    /// An estimate of the memory taken by the table of positions.
+    pub table_bytes: usize,
+    /// The move from the root being searched, counting from 1.
+    pub root_move: usize,
+    pub root_moves: usize,
+}
+
+/// Writes a `Progress` line every `interval`.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    pub format: ProgressFormat,
+    pub interval: Duration,
+    started: Instant,
+    last: Instant,
+    last_nodes: usize,
+}
+
+impl ProgressReporter {
+    pub fn new(format: ProgressFormat, interval: Duration) -> Self {
+        Self {
+            format,
+            interval,
+            started: Instant::now(),
+            last: Instant::now(),
+            last_nodes: 0,
+        }
+    }
+    /// Whether a line is due after `nodes` positions, the clock is only read every few thousand.
+    pub fn due(&self, nodes: usize) -> bool {
+        nodes.is_multiple_of(4096) && self.last.elapsed() >= self.interval
+    }
+    /// Fills in the timings of `progress` and writes it.
+    pub fn report(&mut self, mut progress: Progress) {
+        let now = Instant::now();
+        progress.seconds = (now - self.started).as_secs_f64();
+        progress.nodes_per_second =
+            (progress.nodes - self.last_nodes) as f64 / (now - self.last).as_secs_f64();
+        self.last = now;
+        self.last_nodes = progress.nodes;
+        eprintln!("{}", self.line(&progress));
+    }
+    pub fn line(&self, progress: &Progress) -> String {
+        match self.format {
+            ProgressFormat::Human => format!(
+                "{:.1}s: {} states, {} nodes ({:.0}/s), depth {}, {} colours, ~{} MB, move {}/{}",
+                progress.seconds,
+                progress.states,
+                progress.nodes,
+                progress.nodes_per_second,
+                progress.depth,
+                progress.most_colours,
+                progress.table_bytes >> 20,
+                progress.root_move,
+                progress.root_moves
+            ),
+            ProgressFormat::Json => serde_json::to_string(progress).unwrap(),
+        }
+    }
+}
+
+#[test]
+fn test_progress_line() {
+    let progress = Progress {
+        seconds: 2.5,
+        states: 1000,
+        nodes: 4096,
+        nodes_per_second: 1638.4,
+        depth: 7,
+        most_colours: 4,
+        table_bytes: 3 << 20,
+        root_move: 2,
+        root_moves: 5,
+    };
+    let mut reporter = ProgressReporter::new(ProgressFormat::Human, Duration::ZERO);
+    assert_eq!(
+        reporter.line(&progress),
+        "2.5s: 1000 states, 4096 nodes (1638/s), depth 7, 4 colours, ~3 MB, move 2/5"
+    );
+    reporter.format = ProgressFormat::Json;
+    let json: serde_json::Value = serde_json::from_str(&reporter.line(&progress)).unwrap();
+    assert_eq!(json["states"], 1000);
+    assert_eq!(json["root_move"], 2);
+    assert_eq!(json["nodes_per_second"], 1638.4);
+
+    assert!(!reporter.due(4095));
+    assert!(reporter.due(4096));
+}
@@ -1,8 +1,15 @@
-use ahash::HashMap;
+use ahash::{HashMap, RandomState};
 
-use crate::simple_state::{
-    state::{find_barrier::FindBarrier, State},
-    Move, StateWithMove,
+use crate::{
+    disk_store::Record,
+    limits::{Budget, SearchLimits, SearchOutcome},
+    presentation::Presentation,
+    progress::{Progress, ProgressReporter},
+    simple_state::{
+        state::{find_barrier::FindBarrier, State},
+        Move, StateWithMove,
+    },
+    table::{Replaceable, StateStore, Table},
 };
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -17,6 +24,53 @@ pub enum Visited {
     },
 }
 
+impl Replaceable for Visited {
+    /// Losing states can be searched again, winning ones are needed to print the strategy.
+    fn replaceable(&self) -> bool {
+        matches!(self, Visited::Losing)
+    }
+}
+
+impl Record for Visited {
+    const SIZE: usize = 5;
+    fn write(&self, bytes: &mut [u8]) {
+        match *self {
+            Visited::No => bytes[0] = 0,
+            Visited::Active => bytes[0] = 1,
+            Visited::Losing => bytes[0] = 2,
+            Visited::Winning { move_, barrier } => {
+                bytes.copy_from_slice(&[3, move_.0, move_.1, barrier.front, barrier.back])
+            }
+        }
+    }
+    fn read(bytes: &[u8]) -> Self {
+        match bytes[0] {
+            0 => Visited::No,
+            1 => Visited::Active,
+            2 => Visited::Losing,
+            _ => Visited::Winning {
+                move_: Move(bytes[1], bytes[2]),
+                barrier: FindBarrier {
+                    front: bytes[3],
+                    back: bytes[4],
+                },
+            },
+        }
+    }
+}
+
+impl<const MAX_CLIQUE: u32> Record for State<MAX_CLIQUE> {
+    const SIZE: usize = 16;
+    fn write(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.data.to_le_bytes());
+    }
+    fn read(bytes: &[u8]) -> Self {
+        Self {
+            data: u128::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+}
+
 impl Visited {
     pub fn to_find_result(&self) -> FindStateResult {
         match *self {
@@ -32,16 +86,46 @@ pub enum Reduction {
     Back,
 }
 
-#[derive(Debug, Default, Clone)]
+/// Not `Clone`, as the positions may be kept on disk.
+#[derive(Debug)]
 pub struct SearchState<const MAX_CLIQUE: u32> {
-    pub map: HashMap<State<MAX_CLIQUE>, Visited>,
+    /// An unbounded `Table` by default.
+    pub map: Box<dyn StateStore<State<MAX_CLIQUE>, Visited>>,
     pub reductees: HashMap<State<MAX_CLIQUE>, (State<MAX_CLIQUE>, Reduction)>,
+    pub presentation: Presentation,
+    pub limits: SearchLimits,
+    budget: Budget,
+    /// Reports on the search while it runs.
+    pub progress: Option<ProgressReporter>,
+    nodes: usize,
+    /// Moves from the root to the state being expanded.
+    ply: usize,
+    most_colours: u8,
+    root_move: (usize, usize),
+}
+
+impl<const MAX_CLIQUE: u32> Default for SearchState<MAX_CLIQUE> {
+    fn default() -> Self {
+        Self {
+            map: Box::new(Table::<State<MAX_CLIQUE>, Visited, RandomState>::default()),
+            reductees: HashMap::default(),
+            presentation: Presentation::default(),
+            limits: SearchLimits::default(),
+            budget: Budget::default(),
+            progress: None,
+            nodes: 0,
+            ply: 0,
+            most_colours: 0,
+            root_move: (0, 0),
+        }
+    }
 }
 
 impl<const MAX_CLIQUE: u32> SearchState<MAX_CLIQUE> {
-    pub fn get_winning(&self, state: &State<MAX_CLIQUE>) -> Option<&Visited> {
+    pub fn get_winning(&self, state: &State<MAX_CLIQUE>) -> Option<Visited> {
         match self.map.get(state) {
             v @ Some(_) => v,
+            None if !self.presentation.is_symmetric() => None,
             None => {
                 let mut flip = *state;
                 flip.normalize_inner(false);
@@ -49,6 +133,48 @@ impl<const MAX_CLIQUE: u32> SearchState<MAX_CLIQUE> {
             }
         }
     }
+    /// Normalizes `state`, flipping it only when the presentation keeps the mirror symmetry.
+    pub fn normalize(&self, state: &mut State<MAX_CLIQUE>) -> bool {
+        state.normalize_inner(self.presentation.is_symmetric())
+    }
+    /// `State::find_strategy` within `limits`. Calling it again resumes the search with a fresh budget.
+    pub fn solve(
+        &mut self,
+        state: &State<MAX_CLIQUE>,
+        depth: usize,
+        max_size: u8,
+    ) -> SearchOutcome {
+        self.budget = Budget::default();
+        let result = state.find_strategy(self, depth, max_size);
+        self.budget
+            .outcome(matches!(result, FindStateResult::True(_)))
+    }
+    /// States expanded so far.
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+    fn update_progress(&mut self, state: &State<MAX_CLIQUE>) {
+        self.most_colours = self.most_colours.max(state.colours_used_count());
+        if !self.progress.as_ref().is_some_and(|p| p.due(self.nodes)) {
+            return;
+        }
+        let progress = Progress {
+            seconds: 0.0,
+            states: self.map.len(),
+            nodes: self.nodes,
+            nodes_per_second: 0.0,
+            depth: self.ply,
+            most_colours: self.most_colours as usize,
+            table_bytes: self.table_bytes(),
+            root_move: self.root_move.0,
+            root_moves: self.root_move.1,
+        };
+        self.progress.as_mut().unwrap().report(progress);
+    }
+    /// An estimate of the memory taken by `map`.
+    pub fn table_bytes(&self) -> usize {
+        self.map.bytes()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -65,7 +191,7 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
         max_size: u8,
     ) -> FindStateResult {
         let mut norm = *self;
-        let was_flipped = norm.normalize();
+        let was_flipped = search_state.normalize(&mut norm);
         let result = norm.find_strategy_inner(search_state, depth, max_size);
         match result {
             FindStateResult::True(mut barrier) => {
@@ -86,7 +212,7 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
         if depth == 0 {
             return FindStateResult::False;
         }
-        match search_state.map.get(&self).copied().unwrap_or_default() {
+        match search_state.map.get(self).unwrap_or_default() {
             Visited::Winning { barrier, .. } => return FindStateResult::True(barrier),
             Visited::Losing => return FindStateResult::False,
             Visited::Active => return FindStateResult::False,
@@ -94,6 +220,16 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
                 search_state.map.insert(*self, Visited::Active);
             }
         }
+        search_state.nodes += 1;
+        let nodes = search_state.nodes;
+        let table_bytes = search_state.table_bytes();
+        if search_state.budget.spend(&search_state.limits, table_bytes) {
+            search_state.map.remove(self);
+            return FindStateResult::False;
+        }
+        if search_state.progress.is_some() {
+            search_state.update_progress(self);
+        }
 
         // if self.size() == max_size {
         //     let cloned = *self;
@@ -106,24 +242,46 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
         //     return cloned.find_strategy(search_state, depth - 1, max_size);
         // }
 
-        let mut moves = self.moves().collect::<Vec<_>>();
-        moves.sort_by_key(|sm| sm.preferable_order());
-
-        for move_ in moves {
-            if let FindStateResult::True(barrier) =
-                move_.find_strategy(search_state, depth, max_size)
-            {
-                search_state.map.insert(
-                    *self,
-                    Visited::Winning {
-                        move_: move_.move_,
-                        barrier,
-                    },
-                );
+        // Built once, the colours of every move are needed again for each response.
+        let mut moves = self.move_table().moves;
+        let presentation = search_state.presentation;
+        moves.retain(|(move_, _)| {
+            presentation.allows(
+                move_.0 as usize,
+                move_.1 as usize,
+                (0..self.len()).map(|i| self.is_start_at(i)),
+            )
+        });
+        moves.sort_by_key(|&(move_, colours)| self.with_move(move_).preferable_order(colours));
+
+        let root_moves = moves.len();
+        for (i, (move_, colours)) in moves.into_iter().enumerate() {
+            if search_state.ply == 0 {
+                search_state.root_move = (i + 1, root_moves);
+            }
+            search_state.ply += 1;
+            let result = self.with_move(move_).find_strategy_with_colours(
+                colours,
+                search_state,
+                depth,
+                max_size,
+            );
+            search_state.ply -= 1;
+            if let FindStateResult::True(barrier) = result {
+                search_state
+                    .map
+                    .insert(*self, Visited::Winning { move_, barrier });
                 return FindStateResult::True(barrier);
             }
+            if search_state.budget.exhausted() {
+                search_state.map.remove(self);
+                return FindStateResult::False;
+            }
         }
-        search_state.map.insert(*self, Visited::Losing);
+        let work = search_state.nodes - nodes;
+        search_state
+            .map
+            .insert_with_work(*self, Visited::Losing, work);
         FindStateResult::False
     }
 }
@@ -135,11 +293,22 @@ impl<'a, const MAX_CLIQUE: u32> StateWithMove<'a, MAX_CLIQUE> {
         depth: usize,
         max_size: u8,
     ) -> FindStateResult {
-        let mut barrier = self.find_barrier();
-        for color in self
+        let colours = self
             .state
             .allowed_colours_for_segment(self.move_.0, self.move_.1)
-        {
+            .fold(0, |acc, c| acc | 1 << c);
+        self.find_strategy_with_colours(colours, search_state, depth, max_size)
+    }
+    /// Like `find_strategy` with the colours the Algorithm may answer with already known.
+    fn find_strategy_with_colours(
+        &self,
+        colours: u8,
+        search_state: &mut SearchState<MAX_CLIQUE>,
+        depth: usize,
+        max_size: u8,
+    ) -> FindStateResult {
+        let mut barrier = self.find_barrier();
+        for color in (0..8).filter(|c| colours & (1 << c) != 0) {
             let mut clone = *self.state;
             clone.insert_segment(self.move_.0, self.move_.1, color);
             match clone.find_strategy(search_state, depth - 1, max_size) {
@@ -149,10 +318,10 @@ impl<'a, const MAX_CLIQUE: u32> StateWithMove<'a, MAX_CLIQUE> {
         }
         return FindStateResult::True(barrier);
     }
-    fn preferable_order(&self) -> (u8, i8) {
+    fn preferable_order(&self, colours: u8) -> (u32, i8) {
         let confining_factor= self.move_.0 - self.state.limit_front() + self.state.limit_back()
             - self.move_.1;
-        (self.allowed_colours_count(), -(confining_factor as i8))
+        (colours.count_ones(), -(confining_factor as i8))
     }
     fn find_barrier(&self) -> FindBarrier {
         self.state.find_barrier(self.move_.0, self.move_.1)
@@ -166,3 +335,86 @@ fn test() {
     s.normalize_inner(false);
     panic!("{}", s.to_string())
 }
+
+#[test]
+fn test_ordered_presentation() {
+    let mut search_state = SearchState::<2>::default();
+    let result = State::<2>::new().find_strategy(&mut search_state, 5, 7);
+    assert!(matches!(result, FindStateResult::True(_)));
+    for presentation in [Presentation::LeftEndpoint, Presentation::RightEndpoint] {
+        let mut search_state = SearchState::<2> {
+            presentation,
+            ..Default::default()
+        };
+        let result = State::<2>::new().find_strategy(&mut search_state, 7, 7);
+        assert!(matches!(result, FindStateResult::False));
+    }
+}
+
+#[test]
+fn test_search_limits() {
+    let mut search_state = SearchState::<2> {
+        limits: SearchLimits {
+            nodes: Some(8),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(
+        search_state.solve(&State::new(), 5, 7),
+        SearchOutcome::Unknown
+    );
+    while search_state.solve(&State::new(), 5, 7) == SearchOutcome::Unknown {}
+    search_state
+        .map
+        .for_each(&mut |_, v| assert!(!matches!(v, Visited::Active)));
+    assert_eq!(search_state.solve(&State::new(), 5, 7), SearchOutcome::Win);
+}
+
+#[test]
+fn test_bounded_map() {
+    for presentation in [Presentation::Any, Presentation::LeftEndpoint] {
+        let mut unbounded = SearchState::<2> {
+            presentation,
+            ..Default::default()
+        };
+        let expected = State::<2>::new().find_strategy(&mut unbounded, 7, 7);
+        let mut search_state = SearchState::<2> {
+            presentation,
+            map: Box::new(Table::<_, _, RandomState>::bounded(1 << 8)),
+            ..Default::default()
+        };
+        let result = State::<2>::new().find_strategy(&mut search_state, 7, 7);
+        assert_eq!(
+            matches!(result, FindStateResult::True(_)),
+            matches!(expected, FindStateResult::True(_))
+        );
+        assert!(search_state.map.len() <= unbounded.map.len());
+        if let FindStateResult::True(_) = result {
+            State::<2>::new().print_strategy(&search_state, &mut std::io::sink());
+        } else {
+            assert!(search_state.map.evicted() > 0);
+        }
+    }
+}
+
+#[test]
+fn test_disk_map() {
+    let mut in_memory = SearchState::<2>::default();
+    let expected = State::<2>::new().find_strategy(&mut in_memory, 7, 7);
+    let dir = std::env::temp_dir().join(format!("find-disk-store-{}", std::process::id()));
+    let mut search_state = SearchState::<2> {
+        map: Box::new(
+            crate::disk_store::DiskStore::<_, _, RandomState>::new(&dir, 1 << 10).unwrap(),
+        ),
+        ..Default::default()
+    };
+    let result = State::<2>::new().find_strategy(&mut search_state, 7, 7);
+    assert_eq!(
+        matches!(result, FindStateResult::True(_)),
+        matches!(expected, FindStateResult::True(_))
+    );
+    assert_eq!(search_state.map.len(), in_memory.map.len());
+    drop(search_state);
+    std::fs::remove_dir(&dir).unwrap();
+}
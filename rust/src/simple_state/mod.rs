@@ -1,4 +1,4 @@
-use crate::simple_state::state::State;
+use crate::{presentation::Presentation, simple_state::state::State};
 
 pub mod find;
 pub mod print_strategy;
@@ -40,9 +40,21 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
     pub fn moves<'a>(
         &'a self,
     ) -> impl Iterator<Item = StateWithMove<'a, MAX_CLIQUE>> + use<'a, MAX_CLIQUE> {
-        (self.limit_front()..=self.limit_back()).flat_map(move |start| {
-            let (a, b) = self.valid_segment_ends(start);
-            (a..b).map(move |end| self.with_move(Move(start, end)))
+        self.move_table()
+            .moves
+            .into_iter()
+            .map(move |(move_, _)| self.with_move(move_))
+    }
+    pub fn moves_in_order<'a>(
+        &'a self,
+        presentation: Presentation,
+    ) -> impl Iterator<Item = StateWithMove<'a, MAX_CLIQUE>> + use<'a, MAX_CLIQUE> {
+        self.moves().filter(move |m| {
+            presentation.allows(
+                m.move_.0 as usize,
+                m.move_.1 as usize,
+                (0..self.len()).map(|i| self.is_start_at(i)),
+            )
         })
     }
     pub fn front_moves<'a>(
@@ -22,7 +22,7 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
         printer: &mut StrategyGraphPrinter<MAX_CLIQUE>,
     ) {
         let mut norm = *self;
-        norm.normalize();
+        search_state.normalize(&mut norm);
         let Some(Visited::Winning { move_, .. }) = search_state.get_winning(&norm) else {
             panic!("Strategy incomplete! {}", &norm)
         };
@@ -37,13 +37,13 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
         }
         printer.vertices[size].push(norm);
 
-        let outcomes: Vec<_> = norm.with_move(*move_).outcomes_with_colours().collect();
+        let outcomes: Vec<_> = norm.with_move(move_).outcomes_with_colours().collect();
 
         if outcomes.is_empty() {
             let mut result = norm;
             let colour = result.colours_used_count();
             result.insert_segment(move_.0, move_.1, colour);
-            result.normalize();
+            search_state.normalize(&mut result);
 
             if !printer.printed.contains(&result) {
                 printer.printed.insert(result);
@@ -63,7 +63,7 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
 
         for (child, c) in outcomes {
             let mut child_norm = child;
-            child_norm.normalize();
+            search_state.normalize(&mut child_norm);
             printer.edges.push(StrategyGraphEdge {
                 source: norm,
                 target: child_norm,
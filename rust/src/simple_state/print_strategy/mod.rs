@@ -21,7 +21,7 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
         printed: &mut HashSet<State<MAX_CLIQUE>>,
     ) {
         let mut norm = *self;
-        norm.normalize();
+        search_state.normalize(&mut norm);
         let Some(Visited::Winning { move_, .. }) = search_state.map.get(&norm) else {
             panic!("Strategy incomplete! {}", &norm)
         };
@@ -30,7 +30,7 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
         }
         writeln!(w, "{} {} {}", norm.to_string(), move_.0, move_.1).unwrap();
         printed.insert(norm);
-        for child in norm.with_move(*move_).outcomes() {
+        for child in norm.with_move(move_).outcomes() {
             child.print_strategy_inner(search_state, w, printed);
         }
     }
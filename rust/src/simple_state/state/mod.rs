@@ -4,6 +4,7 @@ mod test;
 pub mod find_barrier;
 pub mod generate_all;
 pub mod hash;
+pub mod move_table;
 pub mod string;
 
 // Each `Event` is 4 bits,
@@ -314,6 +315,10 @@ impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
         }
     }
     #[inline(always)]
+    pub fn is_start_at(&self, index: u8) -> bool {
+        event_is_start(self.get_at_index(index))
+    }
+    #[inline(always)]
     fn get_at_index(&self, index: u8) -> u8 {
         let shift = index * 4;
         ((self.data() >> shift) & 0b1111) as u8
@@ -0,0 +1,78 @@
+use crate::simple_state::{
+    state::{event_is_start, State},
+    Move,
+};
+
+/// Every move Spoiler can make from a state, with the colours the Algorithm may answer each with.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MoveTable {
+    pub moves: Vec<(Move, u8)>,
+}
+
+impl<const MAX_CLIQUE: u32> State<MAX_CLIQUE> {
+    /// Builds the `MoveTable` in one pass over the events.
+    /// The ends and the points where the clique is full are kept as bitmaps,
+    /// so the range of ends for each start is found with a few `trailing_zeros`
+    /// instead of walking the events like `valid_segment_ends` does.
+    pub fn move_table(&self) -> MoveTable {
+        let len = self.len() as u32;
+        // Bit `i` is set when the event at `i` is an end.
+        let mut ends = 0u32;
+        // Bit `i` is set when `MAX_CLIQUE` segments are open just before the event at `i`.
+        let mut full = 0u32;
+        let mut masks = [0u8; 32];
+        let mut mask = 0u8;
+        let mut open = 0;
+        let mut data = self.data();
+        for i in 0..len {
+            masks[i as usize] = mask;
+            if open >= MAX_CLIQUE {
+                full |= 1 << i;
+            }
+            let event = (data & 0b1111) as u8;
+            data >>= 4;
+            if event_is_start(event) {
+                mask |= 1 << event;
+                open += 1;
+            } else {
+                ends |= 1 << i;
+                mask &= !(1 << (event & 0b111));
+                open -= 1;
+            }
+        }
+        let used = masks.iter().fold(0, |acc, m| acc | m).count_ones() as u8;
+        let colours = ((1u16 << (Self::EXPECTED_COLOURS - 1).min(used + 1)) - 1) as u8;
+        let limit_back = self.limit_back() as u32;
+
+        let mut moves = vec![];
+        for start in self.limit_front() as u32..=limit_back {
+            if full >> start & 1 != 0 {
+                continue;
+            }
+            // The new segment has to outlast every segment open at its start.
+            let opened = start - 2 * (ends & ((1 << start) - 1)).count_ones();
+            let min_end = if opened == 0 {
+                start
+            } else {
+                let mut later_ends = ends >> start;
+                for _ in 1..opened {
+                    later_ends &= later_ends - 1;
+                }
+                let last_end = start + later_ends.trailing_zeros();
+                let crossed = ((1 << (last_end + 2)) - 1) & !((1 << (start + 1)) - 1);
+                if later_ends == 0 || last_end >= limit_back || full & crossed != 0 {
+                    continue;
+                }
+                last_end + 1
+            };
+            // And mustn't contain a whole one or fill up the clique.
+            let stops = (ends | full >> 1) >> min_end;
+            let max_end = (min_end + stops.trailing_zeros()).min(limit_back);
+            for end in min_end..=max_end {
+                let allowed = !masks[start as usize] & !masks[end as usize] & colours;
+                moves.push((Move(start as u8, end as u8), allowed));
+            }
+        }
+        MoveTable { moves }
+    }
+}
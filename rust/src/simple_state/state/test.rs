@@ -221,3 +221,37 @@ fn test_allowed_segment_ends_clique() {
         )
     }
 }
+
+#[test]
+fn test_move_table() {
+    use crate::simple_state::Move;
+
+    fn check<const K: u32>(state: State<K>, depth: usize) {
+        let mut expected = vec![];
+        for start in state.limit_front()..=state.limit_back() {
+            let (a, b) = state.valid_segment_ends(start);
+            for end in a..b {
+                let colours = state
+                    .allowed_colours_for_segment(start, end)
+                    .fold(0, |acc, c| acc | 1 << c);
+                expected.push((Move(start, end), colours));
+            }
+        }
+        let table = state.move_table();
+        assert_eq!(table.moves, expected, "{state}");
+        if depth == 0 {
+            return;
+        }
+        for (Move(start, end), colours) in table.moves {
+            for colour in (0..8).filter(|c| colours & (1 << c) != 0) {
+                let mut next = state;
+                next.insert_segment(start, end, colour);
+                check(next, depth - 1);
+            }
+        }
+    }
+    for base in ["[]", "A[aBb]", "[AaB]b", "A[BaCb]c", "AB[abCc]"] {
+        check(State::<2>::from_string(base), 3);
+        check(State::<3>::from_string(base), 3);
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::hash::GlobalRandomState;
+
+/// Where a search keeps the positions it has met, see `Table` and `DiskStore`.
+/// Stores built on I/O panic when it fails.
+pub trait StateStore<K, V>: Debug {
+    fn get(&self, key: &K) -> Option<V>;
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+    /// Inserts an entry that took `work` positions to settle.
+    fn insert_with_work(&mut self, key: K, value: V, work: usize);
+    fn insert(&mut self, key: K, value: V) {
+        self.insert_with_work(key, value, 0);
+    }
+    fn remove(&mut self, key: &K) -> Option<V>;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// An estimate of the memory taken by the store.
+    fn bytes(&self) -> usize;
+    /// How many entries had to make room for others.
+    fn evicted(&self) -> usize {
+        0
+    }
+    fn for_each(&self, f: &mut dyn FnMut(&K, &V));
+    fn retain(&mut self, keep: &mut dyn FnMut(&K, &V) -> bool);
+}
+
+/// Entries a bounded `Table` may evict when it needs room. The others are pinned.
+pub trait Replaceable {
+    fn replaceable(&self) -> bool;
+}
+
+const BUCKET_SLOTS: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct Slot<K, V> {
+    key: K,
+    value: V,
+    /// Positions expanded to settle the entry, the cheapest to settle again is evicted first.
+    work: usize,
+}
+
+type Bucket<K, V> = [Option<Slot<K, V>>; BUCKET_SLOTS];
+
+/// A transposition table. Unbounded by default, like a `HashMap`.
+/// A bounded one keeps replaceable entries in a fixed number of buckets, and a new entry takes
+/// the place of the one that took the least work in a full bucket. Pinned entries are always kept.
+#[derive(Debug, Clone)]
+pub struct Table<K, V, S = GlobalRandomState> {
+    pinned: HashMap<K, V, S>,
+    buckets: Vec<Bucket<K, V>>,
+    in_buckets: usize,
+    evicted: usize,
+    hasher: S,
+}
+
+impl<K, V, S: Default> Default for Table<K, V, S> {
+    fn default() -> Self {
+        Self {
+            pinned: HashMap::default(),
+            buckets: vec![],
+            in_buckets: 0,
+            evicted: 0,
+            hasher: S::default(),
+        }
+    }
+}
+
+impl<K, V, S> Table<K, V, S>
+where
+    K: Hash + Eq + Copy,
+    V: Replaceable + Copy,
+    S: BuildHasher + Default,
+{
+    /// Replaceable entries take at most `bytes`, pinned ones as much as they need besides.
+    pub fn bounded(bytes: usize) -> Self {
+        let buckets = (bytes / std::mem::size_of::<Bucket<K, V>>()).max(1);
+        Self {
+            buckets: vec![[None; BUCKET_SLOTS]; buckets],
+            ..Default::default()
+        }
+    }
+    pub fn is_bounded(&self) -> bool {
+        !self.buckets.is_empty()
+    }
+    fn bucket(&self, key: &K) -> usize {
+        self.hasher.hash_one(key) as usize % self.buckets.len()
+    }
+    fn slot(&self, key: &K) -> Option<&Slot<K, V>> {
+        if !self.is_bounded() {
+            return None;
+        }
+        self.buckets[self.bucket(key)]
+            .iter()
+            .flatten()
+            .find(|slot| slot.key == *key)
+    }
+    pub fn get(&self, key: &K) -> Option<&V> {
+        match self.pinned.get(key) {
+            Some(value) => Some(value),
+            None => self.slot(key).map(|slot| &slot.value),
+        }
+    }
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+    pub fn insert(&mut self, key: K, value: V) {
+        self.insert_with_work(key, value, 0);
+    }
+    /// Inserts an entry that took `work` positions to settle.
+    pub fn insert_with_work(&mut self, key: K, value: V, work: usize) {
+        if !self.is_bounded() || !value.replaceable() {
+            self.remove_from_bucket(&key);
+            self.pinned.insert(key, value);
+            return;
+        }
+        self.pinned.remove(&key);
+        let bucket = self.bucket(&key);
+        let slots = &mut self.buckets[bucket];
+        let slot = match slots.iter().position(|s| s.is_some_and(|s| s.key == key)) {
+            Some(same) => same,
+            None => match slots.iter().position(Option::is_none) {
+                Some(empty) => {
+                    self.in_buckets += 1;
+                    empty
+                }
+                None => {
+                    self.evicted += 1;
+                    (0..BUCKET_SLOTS)
+                        .min_by_key(|&i| slots[i].map_or(0, |s| s.work))
+                        .unwrap()
+                }
+            },
+        };
+        slots[slot] = Some(Slot { key, value, work });
+    }
+    fn remove_from_bucket(&mut self, key: &K) -> Option<V> {
+        if !self.is_bounded() {
+            return None;
+        }
+        let bucket = self.bucket(key);
+        let slot = self.buckets[bucket]
+            .iter_mut()
+            .find(|s| s.is_some_and(|s| s.key == *key))?;
+        self.in_buckets -= 1;
+        slot.take().map(|slot| slot.value)
+    }
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.pinned
+            .remove(key)
+            .or_else(|| self.remove_from_bucket(key))
+    }
+    pub fn len(&self) -> usize {
+        self.pinned.len() + self.in_buckets
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// How many entries had to make room for others.
+    pub fn evicted(&self) -> usize {
+        self.evicted
+    }
+    /// An estimate of the memory taken by the table.
+    pub fn bytes(&self) -> usize {
+        // A control byte for every slot of the map besides the entry.
+        self.pinned.capacity() * (std::mem::size_of::<(K, V)>() + 1)
+            + self.buckets.len() * std::mem::size_of::<Bucket<K, V>>()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.pinned.iter().chain(
+            self.buckets
+                .iter()
+                .flatten()
+                .flatten()
+                .map(|slot| (&slot.key, &slot.value)),
+        )
+    }
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &V) -> bool) {
+        self.pinned.retain(|key, value| keep(key, value));
+        for slot in self.buckets.iter_mut().flatten() {
+            if slot.is_some_and(|s| !keep(&s.key, &s.value)) {
+                *slot = None;
+                self.in_buckets -= 1;
+            }
+        }
+    }
+}
+
+impl<K, V, S> StateStore<K, V> for Table<K, V, S>
+where
+    K: Hash + Eq + Copy + Debug,
+    V: Replaceable + Copy + Debug,
+    S: BuildHasher + Default + Debug,
+{
+    fn get(&self, key: &K) -> Option<V> {
+        Table::get(self, key).copied()
+    }
+    fn contains_key(&self, key: &K) -> bool {
+        Table::contains_key(self, key)
+    }
+    fn insert_with_work(&mut self, key: K, value: V, work: usize) {
+        Table::insert_with_work(self, key, value, work)
+    }
+    fn remove(&mut self, key: &K) -> Option<V> {
+        Table::remove(self, key)
+    }
+    fn len(&self) -> usize {
+        Table::len(self)
+    }
+    fn bytes(&self) -> usize {
+        Table::bytes(self)
+    }
+    fn evicted(&self) -> usize {
+        Table::evicted(self)
+    }
+    fn for_each(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, value) in self.iter() {
+            f(key, value);
+        }
+    }
+    fn retain(&mut self, keep: &mut dyn FnMut(&K, &V) -> bool) {
+        Table::retain(self, keep)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Entry {
+        Won,
+        Lost(usize),
+    }
+
+    impl Replaceable for Entry {
+        fn replaceable(&self) -> bool {
+            matches!(self, Entry::Lost(_))
+        }
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut table = Table::<u32, Entry>::default();
+        for i in 0..1000 {
+            table.insert(i, Entry::Lost(i as usize));
+        }
+        table.insert(5, Entry::Won);
+        assert_eq!(table.len(), 1000);
+        assert_eq!(table.get(&5), Some(&Entry::Won));
+        assert_eq!(table.remove(&7), Some(Entry::Lost(7)));
+        assert!(!table.contains_key(&7));
+        assert_eq!(table.evicted(), 0);
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut table = Table::<u32, Entry>::bounded(1000);
+        let slots = table.buckets.len() * BUCKET_SLOTS;
+        for i in 0..100 {
+            table.insert(i, Entry::Won);
+        }
+        for i in 100..10_000 {
+            table.insert_with_work(i, Entry::Lost(i as usize), (i % 7) as usize);
+        }
+        // Wins are pinned, losses share the buckets.
+        assert!((0..100).all(|i| table.get(&i) == Some(&Entry::Won)));
+        assert!(table.len() <= 100 + slots);
+        assert_eq!(table.len(), table.iter().count());
+        assert_eq!(table.evicted(), 9_900 - (table.len() - 100));
+        assert!(table.bytes() < 1000 + 100 * 64);
+        // What took the most work to settle stays longest.
+        let kept = table.iter().filter(|(_, v)| **v != Entry::Won).count();
+        let heavy = table
+            .iter()
+            .filter(|(&k, v)| **v != Entry::Won && k % 7 == 6)
+            .count();
+        assert!(heavy * 4 > kept);
+
+        // A win replaces the loss of the same position.
+        let lost = *table.iter().find(|(_, v)| **v != Entry::Won).unwrap().0;
+        let len = table.len();
+        table.insert(lost, Entry::Won);
+        assert_eq!(table.len(), len);
+        assert_eq!(table.get(&lost), Some(&Entry::Won));
+
+        table.retain(|_, v| *v == Entry::Won);
+        assert_eq!(table.len(), 101);
+    }
+}